@@ -4,23 +4,24 @@ use bybit_rs::{
     rest::{OrderType, Rest, RestBuilder, RestResponse, TimeInForce},
 };
 use dotenv::dotenv;
-use std::{env, error::Error};
+use std::error::Error;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenv().ok();
     pretty_env_logger::init();
 
-    let api: API = API {
-        key: env::var("TESTNET_API_KEY").unwrap(),
-        secret: env::var("TESTNET_API_SECRET").unwrap(),
-    };
+    let api: API = API::from_env("TESTNET")?;
 
     let rest: Rest = RestBuilder::new()
         .endpoint(Endpoint::TESTNET)
         .api(api)
         .build();
 
+    // fail fast on an expired key
+    let key_info = rest.check_api_key().await?;
+    dbg!(key_info);
+
     // submit order
     let order_response: RestResponse = rest
         .private_order_create(
@@ -0,0 +1,68 @@
+use bybit_rs::{
+    prelude::{Endpoint, Subscription, Symbol, Topic, WebSocket, WebSocketBuilder, WsEvent, API},
+    websocket::store::Store,
+};
+use dotenv::dotenv;
+use futures::StreamExt;
+use std::{error::Error, time::Duration};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    dotenv().ok();
+    pretty_env_logger::init();
+
+    let api: API = API::from_env("TESTNET")?;
+
+    let mut ws: WebSocket = WebSocketBuilder::new()
+        .endpoint(Endpoint::TESTNET)
+        .api(api)
+        .stale_after(Topic::orderbook(200), "BTCUSD", Duration::from_secs(5))
+        .build()
+        .await;
+
+    let subscribed = ws
+        .subscribe_to(vec![
+            Subscription::orderbook(200).symbols(vec![Symbol::BTCUSD, Symbol::ETHUSD]),
+            Subscription::new(Topic::Trade).all(),
+        ])
+        .await?;
+    for (arg, ret_msg) in &subscribed.failed {
+        eprintln!("failed to subscribe to {}: {}", arg, ret_msg);
+    }
+
+    let (mut events, writer) = ws.into_stream();
+    let mut store = Store::default();
+    let mut timer = tokio::time::interval(Duration::from_secs(5));
+
+    let mut ctrl_c = Box::pin(tokio::signal::ctrl_c());
+    let mut closing = false;
+
+    loop {
+        tokio::select! {
+            event = events.next() => {
+                let event = match event {
+                    Some(event) => event?,
+                    None => break Ok(()),
+                };
+                match &event {
+                    WsEvent::Reconnected => println!("reconnected"),
+                    WsEvent::Stale { topic, symbol, silent_for } => {
+                        println!("{:?} {:?} silent for {:?}", topic, symbol, silent_for)
+                    }
+                    _ => {}
+                }
+                store.apply(&event);
+            }
+            _ = &mut ctrl_c, if !closing => {
+                // The stream ends once the socket is closed.
+                closing = true;
+                writer.close().await?;
+            }
+            _ = timer.tick() => {
+                for ((depth, symbol), orderbook) in store.orderbooks() {
+                    println!("{} {:?}: {} price levels", symbol, depth, orderbook.limits.len());
+                }
+            }
+        }
+    }
+}
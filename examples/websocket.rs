@@ -1,19 +1,17 @@
 use bybit_rs::{
     prelude::{Endpoint, Symbol, Topic, WebSocket, WebSocketBuilder, API},
-    websocket::store::take_orderbook,
+    websocket::{store::take_orderbook, OrderBookDepth},
 };
 use dotenv::dotenv;
-use std::{env, error::Error, thread::sleep, time::Duration};
+use std::{error::Error, time::Duration};
+use tokio::time::sleep;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenv().ok();
     pretty_env_logger::init();
 
-    let api: API = API {
-        key: env::var("TESTNET_API_KEY").unwrap(),
-        secret: env::var("TESTNET_API_SECRET").unwrap(),
-    };
+    let api: API = API::from_env("TESTNET")?;
 
     let mut ws: WebSocket = WebSocketBuilder::new()
         .endpoint(Endpoint::TESTNET)
@@ -21,14 +19,18 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .build()
         .await;
 
-    ws.subscribe(vec![Topic::OrderBook200, Topic::Trade], Symbol::BTCUSD)
+    ws.subscribe(vec![Topic::orderbook(200), Topic::Trade], Symbol::BTCUSD)
         .await?;
-    ws.run_forever().await;
+    let handle = ws.run_forever().await;
 
-    (0..=5).for_each(|_| {
-        sleep(Duration::from_secs(5));
-        println!("{:#?}", take_orderbook());
-    });
+    for _ in 0..=5 {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(5)) => println!("{:#?}", take_orderbook(OrderBookDepth::new(200), "BTCUSD")),
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!("{:?}", handle.shutdown().await);
 
     Ok(())
 }
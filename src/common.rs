@@ -1,5 +1,9 @@
+pub(crate) mod credentials;
 pub(crate) mod enums;
+pub(crate) mod secret;
 pub(crate) mod structs;
 
+pub use self::credentials::CredentialsError;
 pub use self::enums::{Side, Symbol};
+pub use self::secret::Secret;
 pub use self::structs::{Endpoint, API};
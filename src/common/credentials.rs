@@ -0,0 +1,332 @@
+use super::secret::Secret;
+use super::structs::API;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, error, fmt, fs, io};
+use zeroize::Zeroizing;
+
+const MAGIC: &[u8; 8] = b"BYBITRS\x01";
+const PBKDF2_ROUNDS: u32 = 600_000;
+/// Files asking for more rounds are rejected rather than left to stall decryption.
+const MAX_PBKDF2_ROUNDS: u32 = 10 * PBKDF2_ROUNDS;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + NONCE_LEN;
+
+#[derive(Debug)]
+pub enum CredentialsError {
+    Io(io::Error),
+    MissingVar(String),
+    MissingProfile(String),
+    MissingField {
+        profile: String,
+        field: &'static str,
+    },
+    InvalidKey(rsa::pkcs1::Error),
+    Decrypt,
+}
+
+impl fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CredentialsError::Io(err) => write!(f, "failed to read credentials: {}", err),
+            CredentialsError::MissingVar(var) => {
+                write!(f, "environment variable {} is not set", var)
+            }
+            CredentialsError::MissingProfile(profile) => {
+                write!(f, "profile [{}] not found in credentials file", profile)
+            }
+            CredentialsError::MissingField { profile, field } => {
+                write!(f, "profile [{}] has no {}", profile, field)
+            }
+            CredentialsError::InvalidKey(err) => write!(f, "invalid RSA private key: {}", err),
+            CredentialsError::Decrypt => {
+                f.write_str("failed to decrypt credentials: wrong passphrase or corrupted file")
+            }
+        }
+    }
+}
+
+impl error::Error for CredentialsError {}
+
+impl From<io::Error> for CredentialsError {
+    fn from(err: io::Error) -> Self {
+        CredentialsError::Io(err)
+    }
+}
+
+impl From<rsa::pkcs1::Error> for CredentialsError {
+    fn from(err: rsa::pkcs1::Error) -> Self {
+        CredentialsError::InvalidKey(err)
+    }
+}
+
+impl API {
+    /// Reads `{prefix}_API_KEY` and `{prefix}_API_SECRET`, e.g. `TESTNET_API_KEY`.
+    ///
+    /// For RSA keys, `{prefix}_API_RSA_PRIVATE_KEY_FILE` may point to a PEM file
+    /// instead of `{prefix}_API_SECRET`.
+    pub fn from_env(prefix: &str) -> Result<Self, CredentialsError> {
+        let var = |name: &str| {
+            if prefix.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{}_{}", prefix, name)
+            }
+        };
+
+        let key_var = var("API_KEY");
+        let key = env::var(&key_var).map_err(|_| CredentialsError::MissingVar(key_var))?;
+
+        let secret_var = var("API_SECRET");
+        let rsa_var = var("API_RSA_PRIVATE_KEY_FILE");
+        let secret = match (env::var(&secret_var), env::var(&rsa_var)) {
+            (Ok(secret), _) => Secret::from(secret),
+            (Err(_), Ok(path)) => read_rsa_pem(Path::new(&path))?,
+            (Err(_), Err(_)) => return Err(CredentialsError::MissingVar(secret_var)),
+        };
+
+        Ok(API { key, secret })
+    }
+
+    /// Reads `profile` from the shared credentials file.
+    ///
+    /// The file is `$BYBIT_CREDENTIALS_FILE` if set, `~/.bybit/credentials` otherwise.
+    pub fn from_profile(profile: &str) -> Result<Self, CredentialsError> {
+        API::from_profile_file(default_credentials_path()?, profile)
+    }
+
+    /// Reads `profile` from an INI-style credentials file:
+    ///
+    /// ```ini
+    /// [default]
+    /// api_key = ...
+    /// api_secret = ...
+    ///
+    /// [rsa]
+    /// api_key = ...
+    /// rsa_private_key_file = /path/to/private.pem
+    /// ```
+    pub fn from_profile_file<P: AsRef<Path>>(
+        path: P,
+        profile: &str,
+    ) -> Result<Self, CredentialsError> {
+        let document = Zeroizing::new(fs::read_to_string(path)?);
+        parse_profile(&document, profile)
+    }
+
+    /// Reads `profile` from a credentials file encrypted with [`API::encrypt_credentials`].
+    pub fn from_encrypted_file<P: AsRef<Path>>(
+        path: P,
+        passphrase: &str,
+        profile: &str,
+    ) -> Result<Self, CredentialsError> {
+        let document = decrypt(&fs::read(path)?, passphrase)?;
+        parse_profile(&document, profile)
+    }
+
+    /// Encrypts a credentials document (see [`API::from_profile_file`]) with a key
+    /// derived from `passphrase` (PBKDF2-HMAC-SHA256, AES-256-GCM).
+    pub fn encrypt_credentials(document: &str, passphrase: &str) -> Vec<u8> {
+        encrypt(document, passphrase, PBKDF2_ROUNDS)
+    }
+}
+
+fn default_credentials_path() -> Result<PathBuf, CredentialsError> {
+    if let Ok(path) = env::var("BYBIT_CREDENTIALS_FILE") {
+        return Ok(PathBuf::from(path));
+    }
+    let home = env::var("HOME").map_err(|_| CredentialsError::MissingVar("HOME".to_owned()))?;
+
+    Ok(Path::new(&home).join(".bybit").join("credentials"))
+}
+
+fn read_rsa_pem(path: &Path) -> Result<Secret, CredentialsError> {
+    let pem = Zeroizing::new(fs::read_to_string(path)?);
+    Ok(Secret::from_rsa_pem(&pem)?)
+}
+
+fn parse_profile(document: &str, profile: &str) -> Result<API, CredentialsError> {
+    let mut current: Option<&str> = None;
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    let mut found = false;
+
+    for line in document.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            current = Some(line[1..line.len() - 1].trim());
+            found |= current == Some(profile);
+            continue;
+        }
+        if current != Some(profile) {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=') {
+            fields.insert(k.trim(), v.trim());
+        }
+    }
+
+    if !found {
+        return Err(CredentialsError::MissingProfile(profile.to_owned()));
+    }
+    let missing = |field| CredentialsError::MissingField {
+        profile: profile.to_owned(),
+        field,
+    };
+
+    let key = fields.get("api_key").ok_or_else(|| missing("api_key"))?;
+    let secret = match (fields.get("api_secret"), fields.get("rsa_private_key_file")) {
+        (Some(secret), _) => Secret::from(*secret),
+        (None, Some(path)) => read_rsa_pem(Path::new(path))?,
+        (None, None) => return Err(missing("api_secret")),
+    };
+
+    Ok(API {
+        key: key.to_string(),
+        secret,
+    })
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, key.as_mut());
+    key
+}
+
+fn encrypt(document: &str, passphrase: &str, rounds: u32) -> Vec<u8> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(passphrase, &salt, rounds);
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).expect("key is 32 bytes");
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), document.as_bytes())
+        .expect("Failed to encrypt credentials");
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&rounds.to_be_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+fn decrypt(data: &[u8], passphrase: &str) -> Result<Zeroizing<String>, CredentialsError> {
+    if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err(CredentialsError::Decrypt);
+    }
+    let (rounds, rest) = data[MAGIC.len()..].split_at(4);
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let rounds = u32::from_be_bytes([rounds[0], rounds[1], rounds[2], rounds[3]]);
+    if !(PBKDF2_ROUNDS..=MAX_PBKDF2_ROUNDS).contains(&rounds) {
+        return Err(CredentialsError::Decrypt);
+    }
+
+    let key = derive_key(passphrase, salt, rounds);
+    let cipher = Aes256Gcm::new_from_slice(key.as_ref()).expect("key is 32 bytes");
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CredentialsError::Decrypt)?,
+    );
+
+    let text = std::str::from_utf8(&plaintext).map_err(|_| CredentialsError::Decrypt)?;
+    Ok(Zeroizing::new(text.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt, parse_profile, CredentialsError, PBKDF2_ROUNDS};
+    use crate::common::{Secret, API};
+    use std::env;
+
+    const DOCUMENT: &str = "
+# bybit credentials
+[default]
+api_key = default-key
+api_secret = default-secret
+
+[testnet]
+api_key = testnet-key
+api_secret = testnet-secret
+
+[broken]
+api_key = broken-key
+";
+
+    #[test]
+    fn test_parse_profile() {
+        let api = parse_profile(DOCUMENT, "testnet").unwrap();
+        assert_eq!(api.key, "testnet-key");
+        assert!(matches!(api.secret, Secret::Hmac(ref s) if s.as_str() == "testnet-secret"));
+
+        assert!(matches!(
+            parse_profile(DOCUMENT, "mainnet"),
+            Err(CredentialsError::MissingProfile(_))
+        ));
+        assert!(matches!(
+            parse_profile(DOCUMENT, "broken"),
+            Err(CredentialsError::MissingField {
+                field: "api_secret",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_from_env() {
+        env::set_var("CREDENTIALS_TEST_API_KEY", "env-key");
+        env::set_var("CREDENTIALS_TEST_API_SECRET", "env-secret");
+
+        let api = API::from_env("CREDENTIALS_TEST").unwrap();
+        assert_eq!(api.key, "env-key");
+        assert!(matches!(
+            API::from_env("CREDENTIALS_MISSING"),
+            Err(CredentialsError::MissingVar(ref var)) if var == "CREDENTIALS_MISSING_API_KEY"
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let encrypted = encrypt(DOCUMENT, "passphrase", PBKDF2_ROUNDS);
+
+        assert_eq!(
+            decrypt(&encrypted, "passphrase").unwrap().as_str(),
+            DOCUMENT
+        );
+        assert!(matches!(
+            decrypt(&encrypted, "wrong passphrase"),
+            Err(CredentialsError::Decrypt)
+        ));
+
+        for rounds in [1_000, 10 * PBKDF2_ROUNDS + 1, u32::MAX] {
+            let mut tampered = encrypted.clone();
+            tampered[8..12].copy_from_slice(&rounds.to_be_bytes());
+            assert!(matches!(
+                decrypt(&tampered, "passphrase"),
+                Err(CredentialsError::Decrypt)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_debug_redacted() {
+        let api = parse_profile(DOCUMENT, "default").unwrap();
+        let debug = format!("{:?}", api);
+
+        assert!(debug.contains("default-key"));
+        assert!(!debug.contains("default-secret"));
+    }
+}
@@ -1,18 +1,15 @@
-#[derive(Clone, Debug)]
+use std::fmt;
+
+#[derive(Clone, Debug, Default)]
 pub enum Side {
+    #[default]
     Buy,
     Sell,
 }
 
-impl Default for Side {
-    fn default() -> Self {
-        Side::Buy
-    }
-}
-
-impl ToString for Side {
-    fn to_string(&self) -> String {
-        String::from(match self {
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             Side::Buy => "Buy",
             Side::Sell => "Sell",
         })
@@ -39,9 +36,9 @@ pub enum Symbol {
     // XEMUSDT,
 }
 
-impl ToString for Symbol {
-    fn to_string(&self) -> String {
-        String::from(match self {
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             Symbol::BTCUSD => "BTCUSD",
             Symbol::ETHUSD => "ETHUSD",
             Symbol::EOSUSD => "EOSUSD",
@@ -53,16 +50,8 @@ impl ToString for Symbol {
     }
 }
 
-impl Into<String> for Symbol {
-    fn into(self) -> String {
-        String::from(match self {
-            Symbol::BTCUSD => "BTCUSD",
-            Symbol::ETHUSD => "ETHUSD",
-            Symbol::EOSUSD => "EOSUSD",
-            Symbol::XRPUSD => "XRPUSD",
-            Symbol::BTCUSDT => "BTCUSDT",
-            Symbol::ETHUSDT => "ETHUSDT",
-            Symbol::BCHUSDT => "BCHUSDT",
-        })
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.to_string()
     }
 }
@@ -1,10 +1,34 @@
+use super::secret::Secret;
 use reqwest::Url;
 use std::collections::BTreeMap;
 
 #[derive(Debug, Clone)]
 pub struct API {
     pub key: String,
-    pub secret: String,
+    pub secret: Secret,
+}
+
+impl API {
+    pub fn new<K, S>(key: K, secret: S) -> Self
+    where
+        K: Into<String>,
+        S: Into<Secret>,
+    {
+        API {
+            key: key.into(),
+            secret: secret.into(),
+        }
+    }
+
+    pub fn with_rsa_pem<K>(key: K, pem: &str) -> Result<Self, rsa::pkcs1::Error>
+    where
+        K: Into<String>,
+    {
+        Ok(API {
+            key: key.into(),
+            secret: Secret::from_rsa_pem(pem)?,
+        })
+    }
 }
 
 #[derive(Clone)]
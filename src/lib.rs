@@ -1,4 +1,6 @@
 mod common;
 pub mod prelude;
 pub mod rest;
+mod runtime;
+pub mod trace;
 pub mod websocket;
@@ -1,5 +1,5 @@
-pub use crate::common::{Endpoint, Side, Symbol, API};
+pub use crate::common::{CredentialsError, Endpoint, Secret, Side, Symbol, API};
 pub use crate::rest::{Rest, RestBuilder};
-pub use crate::websocket::Topic;
+pub use crate::websocket::{Channel, Subscription, Topic};
 pub use crate::websocket::{Limit, OrderBook, Record};
-pub use crate::websocket::{WebSocket, WebSocketBuilder, WebSocketResponse};
+pub use crate::websocket::{WebSocket, WebSocketBuilder, WebSocketResponse, WsEvent};
@@ -1,7 +1,12 @@
 mod core;
 mod enums;
+mod error;
 mod structs;
 
 pub use self::core::{Rest, RestBuilder};
-pub use self::enums::{OrderStatus, OrderType, TimeInForce};
-pub use self::structs::RestResponse;
+pub use self::enums::{
+    Interval, OrderStatus, OrderType, Period, ReturnCode, StopOrderStatus, StopOrderType,
+    TimeInForce,
+};
+pub use self::error::Error;
+pub use self::structs::{ApiKeyInfo, LcpInfo, RestResponse};
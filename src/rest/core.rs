@@ -1,27 +1,28 @@
 use super::{
-    enums::{Interval, OrderStatus, OrderType, Period, TimeInForce},
-    structs::RestResponse,
+    enums::{Interval, OrderStatus, OrderType, Period, ReturnCode, TimeInForce},
+    error::Error,
+    structs::{ApiKeyInfo, LcpInfo, LcpList, RestResponse},
 };
 use crate::common::{Endpoint, Side, Symbol, API};
-use hmac::{Hmac, Mac, NewMac};
+use crate::trace::{self, RateLimit, RestHook, RestRecord};
 use maplit::{btreemap, convert_args};
-use reqwest::{Client, Result};
-use sha2::Sha256;
+use reqwest::{header::HeaderMap, Client, Method, Result, Url};
 use std::collections::BTreeMap;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-
-type HmacSha256 = Hmac<Sha256>;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub struct Rest {
     pub endpoint: Endpoint,
     pub api_key: API,
     pub client: Client,
+    pub hooks: Vec<Arc<dyn RestHook>>,
 }
 
 pub struct RestBuilder<EndpointType, ApiType, Client> {
     endpoint: EndpointType,
     api_key: ApiType,
     client: Client,
+    hooks: Vec<Arc<dyn RestHook>>,
 }
 
 impl RestBuilder<Endpoint, API, Client> {
@@ -30,6 +31,7 @@ impl RestBuilder<Endpoint, API, Client> {
             endpoint: self.endpoint,
             api_key: self.api_key,
             client: self.client,
+            hooks: self.hooks,
         }
     }
 }
@@ -43,16 +45,24 @@ impl RestBuilder<(), (), Client> {
                 .timeout(Duration::from_secs(5))
                 .build()
                 .unwrap(),
+            hooks: Vec::new(),
         }
     }
 }
 
+impl Default for RestBuilder<(), (), Client> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<EndpointType, ApiType, Client> RestBuilder<EndpointType, ApiType, Client> {
     pub fn endpoint(self, endpoint: Endpoint) -> RestBuilder<Endpoint, ApiType, Client> {
         RestBuilder {
             endpoint,
             api_key: self.api_key,
             client: self.client,
+            hooks: self.hooks,
         }
     }
 
@@ -61,8 +71,17 @@ impl<EndpointType, ApiType, Client> RestBuilder<EndpointType, ApiType, Client> {
             endpoint: self.endpoint,
             api_key,
             client: self.client,
+            hooks: self.hooks,
         }
     }
+
+    pub fn hook<H>(mut self, hook: H) -> Self
+    where
+        H: RestHook + 'static,
+    {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
 }
 
 impl Rest {
@@ -87,16 +106,59 @@ impl Rest {
     //     Ok(resp)
     // }
 
+    async fn get(&self, uri: Url) -> Result<RestResponse> {
+        self.send(Method::GET, uri).await
+    }
+
+    async fn post(&self, uri: Url) -> Result<RestResponse> {
+        self.send(Method::POST, uri).await
+    }
+
+    async fn send(&self, method: Method, uri: Url) -> Result<RestResponse> {
+        let request = match method {
+            Method::POST => self
+                .client
+                .post(uri.clone())
+                .header(reqwest::header::CONTENT_LENGTH, 0),
+            _ => self.client.request(method.clone(), uri.clone()),
+        };
+
+        let started = Instant::now();
+        let mut status = None;
+        let mut headers = HeaderMap::new();
+        let result: Result<RestResponse> = async {
+            let resp = request.send().await?;
+            status = Some(resp.status().as_u16());
+            headers = resp.headers().clone();
+            resp.json().await
+        }
+        .await;
+
+        if !self.hooks.is_empty() {
+            let record = RestRecord {
+                method: method.to_string(),
+                path: uri.path().to_owned(),
+                params: trace::redact_params(uri.query_pairs()),
+                status,
+                ret_code: result.as_ref().ok().map(|r| r.ret_code),
+                ret_msg: result.as_ref().ok().map(|r| r.ret_msg.clone()),
+                latency: started.elapsed(),
+                rate_limit: rate_limit(&headers, result.as_ref().ok()),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            };
+            self.hooks.iter().for_each(|hook| hook.on_rest(&record));
+        }
+
+        result
+    }
+
     fn sign(&self, query: &BTreeMap<String, String>) -> String {
         let query_str = query
             .iter()
             .map(|(k, v)| format!("{}={}", k, v))
             .collect::<Vec<String>>()
             .join("&");
-        println!("{}", query_str);
-        let mut mac = HmacSha256::new_varkey(self.api_key.secret.as_bytes()).unwrap();
-        mac.update(query_str.as_bytes());
-        format!("{:x}", mac.finalize().into_bytes())
+        self.api_key.secret.sign(query_str.as_bytes())
     }
 
     fn construct_query<H>(&self, mut query: BTreeMap<H, H>) -> BTreeMap<String, String>
@@ -129,9 +191,9 @@ impl Rest {
         const PATH: &str = "/v2/public/orderBook/L2";
 
         let mut uri = self.endpoint.to_uri();
-        uri.set_query(Some(&format!("symbol={}", symbol.to_string())));
+        uri.set_query(Some(&format!("symbol={}", symbol)));
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -156,7 +218,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -166,10 +228,10 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri();
         if let Some(symbol) = symbol {
-            uri.set_query(Some(&format!("symbol={}", symbol.to_string())));
+            uri.set_query(Some(&format!("symbol={}", symbol)));
         }
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -189,7 +251,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -198,7 +260,7 @@ impl Rest {
         const PATH: &str = "/v2/public/symbols";
 
         let uri = self.endpoint.to_uri().join(PATH).unwrap();
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -230,7 +292,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -255,7 +317,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -280,7 +342,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -305,7 +367,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -328,7 +390,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -349,7 +411,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -372,7 +434,7 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -381,11 +443,12 @@ impl Rest {
         const PATH: &str = "/v2/public/time";
 
         let uri = self.endpoint.to_uri().join(PATH).unwrap();
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn private_order_create(
         &self,
         side: Side,
@@ -432,15 +495,7 @@ impl Rest {
             .to_uri_with_params(self.construct_query(query));
         uri.set_path(PATH);
 
-        let resp = self
-            .client
-            .post(uri)
-            //.header(reqwest::header::CONTENT_TYPE, "application/json")
-            .header(reqwest::header::CONTENT_LENGTH, 0)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp = self.post(uri).await?;
 
         Ok(resp)
     }
@@ -474,7 +529,7 @@ impl Rest {
             .endpoint
             .to_uri_with_params(self.construct_query(query));
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
@@ -498,14 +553,7 @@ impl Rest {
             .endpoint
             .to_uri_with_params(self.construct_query(query));
         uri.set_path(PATH);
-        let resp = self
-            .client
-            .post(uri)
-            .header(reqwest::header::CONTENT_LENGTH, 0)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp = self.post(uri).await?;
 
         Ok(resp)
     }
@@ -520,18 +568,12 @@ impl Rest {
             .endpoint
             .to_uri_with_params(self.construct_query(query));
         uri.set_path(PATH);
-        let resp = self
-            .client
-            .post(uri)
-            .header(reqwest::header::CONTENT_LENGTH, 0)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let resp = self.post(uri).await?;
 
         Ok(resp)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn private_replace_order(
         &self,
         _symbol: Symbol,
@@ -566,10 +608,80 @@ impl Rest {
 
         let mut uri = self.endpoint.to_uri_with_params(query);
         uri.set_path(PATH);
-        let resp = self.client.get(uri).send().await?.json().await?;
+        let resp = self.get(uri).await?;
 
         Ok(resp)
     }
+
+    pub async fn private_api_key_info(&self) -> std::result::Result<Vec<ApiKeyInfo>, Error> {
+        const PATH: &str = "/v2/private/account/api-key";
+
+        let query: BTreeMap<String, String> = BTreeMap::new();
+
+        let mut uri = self
+            .endpoint
+            .to_uri_with_params(self.construct_query(query));
+        uri.set_path(PATH);
+        let resp = self.get(uri).await?;
+
+        resp.into_result()
+    }
+
+    pub async fn private_lcp_info(
+        &self,
+        symbol: Symbol,
+    ) -> std::result::Result<Vec<LcpInfo>, Error> {
+        const PATH: &str = "/v2/private/account/lcp";
+
+        let mut query = BTreeMap::new();
+        query.insert(String::from("symbol"), symbol.to_string());
+
+        let mut uri = self
+            .endpoint
+            .to_uri_with_params(self.construct_query(query));
+        uri.set_path(PATH);
+        let resp = self.get(uri).await?;
+
+        Ok(resp.into_result::<LcpList>()?.lcp_list)
+    }
+
+    /// Fails fast when the configured key is expired (ret_code 33004) or unknown.
+    /// Call it at startup, before trading.
+    pub async fn check_api_key(&self) -> std::result::Result<ApiKeyInfo, Error> {
+        let info = match self.private_api_key_info().await {
+            Ok(info) => info,
+            Err(err) if err.return_code() == Some(ReturnCode::APIKeyExpired) => {
+                return Err(Error::ApiKeyExpired(None))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let info = info
+            .into_iter()
+            .find(|info| info.api_key == self.api_key.key)
+            .ok_or(Error::ApiKeyNotFound)?;
+        if info.is_expired() {
+            return Err(Error::ApiKeyExpired(info.expired_at));
+        }
+
+        Ok(info)
+    }
+}
+
+fn rate_limit(headers: &HeaderMap, resp: Option<&RestResponse>) -> RateLimit {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    RateLimit {
+        limit: header("X-Bapi-Limit")
+            .and_then(|v| v.parse().ok())
+            .or_else(|| resp.and_then(|r| r.rate_limit)),
+        status: header("X-Bapi-Limit-Status")
+            .and_then(|v| v.parse().ok())
+            .or_else(|| resp.and_then(|r| r.rate_limit_status)),
+        reset_ms: header("X-Bapi-Limit-Reset-Timestamp")
+            .and_then(|v| v.parse().ok())
+            .or_else(|| resp.and_then(|r| r.rate_limit_reset_ms.map(|ms| ms as u64))),
+    }
 }
 
 #[cfg(test)]
@@ -583,8 +695,7 @@ mod tests {
 
     fn init() {
         dotenv::dotenv().ok();
-        // let _ = pretty_env_logger::formatted_builder().try_init();
-        pretty_env_logger::init();
+        let _ = pretty_env_logger::try_init();
     }
 
     #[test]
@@ -595,10 +706,7 @@ mod tests {
         hash.insert("key", "value");
 
         let rest = super::RestBuilder::new()
-            .api(API {
-                key: String::from("this-is-key"),
-                secret: String::from("this-is-secret"),
-            })
+            .api(API::new("this-is-key", "this-is-secret"))
             .endpoint(Endpoint::TESTNET)
             .build();
 
@@ -610,10 +718,10 @@ mod tests {
         init();
 
         let rest = super::RestBuilder::new()
-            .api(API {
-                key: env::var("TESTNET_API_KEY").unwrap(),
-                secret: env::var("TESTNET_API_SECRET").unwrap(),
-            })
+            .api(API::new(
+                env::var("TESTNET_API_KEY").unwrap(),
+                env::var("TESTNET_API_SECRET").unwrap(),
+            ))
             .endpoint(Endpoint::TESTNET)
             .build();
 
@@ -628,10 +736,10 @@ mod tests {
         init();
 
         let rest = super::RestBuilder::new()
-            .api(API {
-                key: env::var("TESTNET_API_KEY").unwrap(),
-                secret: env::var("TESTNET_API_SECRET").unwrap(),
-            })
+            .api(API::new(
+                env::var("TESTNET_API_KEY").unwrap(),
+                env::var("TESTNET_API_SECRET").unwrap(),
+            ))
             .endpoint(Endpoint::TESTNET)
             .build();
 
@@ -646,10 +754,10 @@ mod tests {
         init();
 
         let rest = super::RestBuilder::new()
-            .api(API {
-                key: env::var("TESTNET_API_KEY").unwrap(),
-                secret: env::var("TESTNET_API_SECRET").unwrap(),
-            })
+            .api(API::new(
+                env::var("TESTNET_API_KEY").unwrap(),
+                env::var("TESTNET_API_SECRET").unwrap(),
+            ))
             .endpoint(Endpoint::TESTNET)
             .build();
 
@@ -1,3 +1,7 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Interval {
     OneMin,
     ThreeMin,
@@ -14,9 +18,9 @@ pub enum Interval {
     Month,
 }
 
-impl ToString for Interval {
-    fn to_string(&self) -> String {
-        String::from(match self {
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             Interval::OneMin => "1",
             Interval::ThreeMin => "3",
             Interval::FiveMin => "5",
@@ -34,23 +38,34 @@ impl ToString for Interval {
     }
 }
 
-impl Into<String> for Interval {
-    fn into(self) -> String {
-        String::from(match self {
-            Interval::OneMin => "1",
-            Interval::ThreeMin => "3",
-            Interval::FiveMin => "5",
-            Interval::FifteenMin => "15",
-            Interval::ThirtyMin => "30",
-            Interval::OneHour => "60",
-            Interval::TwoHour => "120",
-            Interval::FourHour => "240",
-            Interval::SixHour => "360",
-            Interval::TwelveHour => "720",
-            Interval::Day => "D",
-            Interval::Week => "W",
-            Interval::Month => "M",
-        })
+impl From<Interval> for String {
+    fn from(interval: Interval) -> Self {
+        interval.to_string()
+    }
+}
+
+/// Accepts the codes of derivatives, such as `1` or `D`, and the spot
+/// `klineType`s, such as `1m` or `1d`.
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" | "1m" => Ok(Interval::OneMin),
+            "3" | "3m" => Ok(Interval::ThreeMin),
+            "5" | "5m" => Ok(Interval::FiveMin),
+            "15" | "15m" => Ok(Interval::FifteenMin),
+            "30" | "30m" => Ok(Interval::ThirtyMin),
+            "60" | "1h" => Ok(Interval::OneHour),
+            "120" | "2h" => Ok(Interval::TwoHour),
+            "240" | "4h" => Ok(Interval::FourHour),
+            "360" | "6h" => Ok(Interval::SixHour),
+            "720" | "12h" => Ok(Interval::TwelveHour),
+            "D" | "1d" => Ok(Interval::Day),
+            "W" | "1w" => Ok(Interval::Week),
+            "M" | "1M" => Ok(Interval::Month),
+            _ => Err(format!("Impossible interval: {}", s)),
+        }
     }
 }
 
@@ -63,9 +78,9 @@ pub enum Period {
     OneDay,
 }
 
-impl ToString for Period {
-    fn to_string(&self) -> String {
-        String::from(match self {
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             Period::FiveMin => "5min",
             Period::FifteenMin => "15min",
             Period::ThirtyMin => "30min",
@@ -76,33 +91,40 @@ impl ToString for Period {
     }
 }
 
-impl Into<String> for Period {
-    fn into(self) -> String {
-        String::from(match self {
-            Period::FiveMin => "5min",
-            Period::FifteenMin => "15min",
-            Period::ThirtyMin => "30min",
-            Period::OneHour => "1h",
-            Period::FourHour => "4h",
-            Period::OneDay => "1d",
-        })
+impl From<Period> for String {
+    fn from(period: Period) -> Self {
+        period.to_string()
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OrderType {
     Limit,
     Market,
 }
 
-impl ToString for OrderType {
-    fn to_string(&self) -> String {
-        String::from(match self {
+impl fmt::Display for OrderType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             OrderType::Limit => "Limit",
             OrderType::Market => "Market",
         })
     }
 }
 
+impl FromStr for OrderType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Limit" => Ok(OrderType::Limit),
+            "Market" => Ok(OrderType::Market),
+            _ => Err(format!("Impossible order type: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TimeInForce {
     GoodTillCancel,
     ImmediateOrCancel,
@@ -110,9 +132,9 @@ pub enum TimeInForce {
     PostOnly,
 }
 
-impl ToString for TimeInForce {
-    fn to_string(&self) -> String {
-        String::from(match self {
+impl fmt::Display for TimeInForce {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             TimeInForce::GoodTillCancel => "GoodTillCancel",
             TimeInForce::ImmediateOrCancel => "ImmediateOrCancel",
             TimeInForce::FillOrKill => "FillOrKill",
@@ -121,21 +143,175 @@ impl ToString for TimeInForce {
     }
 }
 
+impl FromStr for TimeInForce {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "GoodTillCancel" => Ok(TimeInForce::GoodTillCancel),
+            "ImmediateOrCancel" => Ok(TimeInForce::ImmediateOrCancel),
+            "FillOrKill" => Ok(TimeInForce::FillOrKill),
+            "PostOnly" => Ok(TimeInForce::PostOnly),
+            _ => Err(format!("Impossible time in force: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OrderStatus {
-    Filled,
+    Created,
     New,
+    Rejected,
+    PartiallyFilled,
+    Filled,
+    PendingCancel,
+    Cancelled,
+    /// Conditional orders only, see [`StopOrderStatus`].
+    Untriggered,
+    Triggered,
+    Deactivated,
+    Active,
 }
 
-impl ToString for OrderStatus {
-    fn to_string(&self) -> String {
-        String::from(match self {
-            OrderStatus::Filled => "Filled",
+impl OrderStatus {
+    /// Filled, cancelled, rejected and deactivated orders will not change anymore.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Rejected
+                | OrderStatus::Filled
+                | OrderStatus::Cancelled
+                | OrderStatus::Deactivated
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            OrderStatus::Created => "Created",
             OrderStatus::New => "New",
+            OrderStatus::Rejected => "Rejected",
+            OrderStatus::PartiallyFilled => "PartiallyFilled",
+            OrderStatus::Filled => "Filled",
+            OrderStatus::PendingCancel => "PendingCancel",
+            OrderStatus::Cancelled => "Cancelled",
+            OrderStatus::Untriggered => "Untriggered",
+            OrderStatus::Triggered => "Triggered",
+            OrderStatus::Deactivated => "Deactivated",
+            OrderStatus::Active => "Active",
+        })
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Created" => Ok(OrderStatus::Created),
+            "New" => Ok(OrderStatus::New),
+            "Rejected" => Ok(OrderStatus::Rejected),
+            "PartiallyFilled" => Ok(OrderStatus::PartiallyFilled),
+            "Filled" => Ok(OrderStatus::Filled),
+            "PendingCancel" => Ok(OrderStatus::PendingCancel),
+            "Cancelled" => Ok(OrderStatus::Cancelled),
+            "Untriggered" => Ok(OrderStatus::Untriggered),
+            "Triggered" => Ok(OrderStatus::Triggered),
+            "Deactivated" => Ok(OrderStatus::Deactivated),
+            "Active" => Ok(OrderStatus::Active),
+            _ => Err(format!("Impossible order status: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopOrderStatus {
+    Untriggered,
+    Triggered,
+    Active,
+    Cancelled,
+    Rejected,
+    Deactivated,
+}
+
+impl StopOrderStatus {
+    /// Only untriggered conditional orders are still waiting for their price.
+    pub fn is_pending(self) -> bool {
+        self == StopOrderStatus::Untriggered
+    }
+}
+
+impl fmt::Display for StopOrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            StopOrderStatus::Untriggered => "Untriggered",
+            StopOrderStatus::Triggered => "Triggered",
+            StopOrderStatus::Active => "Active",
+            StopOrderStatus::Cancelled => "Cancelled",
+            StopOrderStatus::Rejected => "Rejected",
+            StopOrderStatus::Deactivated => "Deactivated",
         })
     }
 }
 
-#[allow(dead_code)]
+impl FromStr for StopOrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Untriggered" => Ok(StopOrderStatus::Untriggered),
+            "Triggered" => Ok(StopOrderStatus::Triggered),
+            "Active" => Ok(StopOrderStatus::Active),
+            "Cancelled" => Ok(StopOrderStatus::Cancelled),
+            "Rejected" => Ok(StopOrderStatus::Rejected),
+            "Deactivated" => Ok(StopOrderStatus::Deactivated),
+            _ => Err(format!("Impossible stop order status: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StopOrderType {
+    #[default]
+    Stop,
+    TakeProfit,
+    StopLoss,
+    TrailingStop,
+    PartialTakeProfit,
+    PartialStopLoss,
+}
+
+impl fmt::Display for StopOrderType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            StopOrderType::Stop => "Stop",
+            StopOrderType::TakeProfit => "TakeProfit",
+            StopOrderType::StopLoss => "StopLoss",
+            StopOrderType::TrailingStop => "TrailingStop",
+            StopOrderType::PartialTakeProfit => "PartialTakeProfit",
+            StopOrderType::PartialStopLoss => "PartialStopLoss",
+        })
+    }
+}
+
+impl FromStr for StopOrderType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Stop" => Ok(StopOrderType::Stop),
+            "TakeProfit" => Ok(StopOrderType::TakeProfit),
+            "StopLoss" => Ok(StopOrderType::StopLoss),
+            "TrailingStop" => Ok(StopOrderType::TrailingStop),
+            "PartialTakeProfit" => Ok(StopOrderType::PartialTakeProfit),
+            "PartialStopLoss" => Ok(StopOrderType::PartialStopLoss),
+            _ => Err(format!("Impossible stop order type: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ReturnCode {
     /// ret_code
     /// Example:
@@ -152,6 +328,17 @@ pub enum ReturnCode {
     /// }
     OK, // 0
     APIKeyExpired, // 33004
+    Other(usize),
+}
+
+impl From<usize> for ReturnCode {
+    fn from(ret_code: usize) -> Self {
+        match ret_code {
+            0 => ReturnCode::OK,
+            33004 => ReturnCode::APIKeyExpired,
+            _ => ReturnCode::Other(ret_code),
+        }
+    }
 }
 
 // pub enum Direction {
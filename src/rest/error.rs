@@ -0,0 +1,51 @@
+use super::enums::ReturnCode;
+use chrono::{DateTime, Utc};
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum Error {
+    Http(reqwest::Error),
+    Api { ret_code: usize, ret_msg: String },
+    Decode(serde_json::Error),
+    ApiKeyExpired(Option<DateTime<Utc>>),
+    ApiKeyNotFound,
+}
+
+impl Error {
+    pub fn return_code(&self) -> Option<ReturnCode> {
+        match self {
+            Error::Api { ret_code, .. } => Some(ReturnCode::from(*ret_code)),
+            Error::ApiKeyExpired(_) => Some(ReturnCode::APIKeyExpired),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "http error: {}", err),
+            Error::Api { ret_code, ret_msg } => write!(f, "{} (ret_code {})", ret_msg, ret_code),
+            Error::Decode(err) => write!(f, "failed to decode result: {}", err),
+            Error::ApiKeyExpired(Some(expired_at)) => {
+                write!(f, "api key expired at {}", expired_at.to_rfc3339())
+            }
+            Error::ApiKeyExpired(None) => f.write_str("api key expired"),
+            Error::ApiKeyNotFound => f.write_str("api key not found in api key info"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Decode(err)
+    }
+}
@@ -1,5 +1,7 @@
+use super::enums::ReturnCode;
+use super::error::Error;
 use chrono::{DateTime, TimeZone, Utc};
-use serde::{self, Deserialize, Deserializer};
+use serde::{self, de::DeserializeOwned, Deserialize, Deserializer};
 use serde_json::Value;
 
 #[derive(Deserialize, Debug)]
@@ -17,11 +19,81 @@ pub struct RestResponse {
     pub rate_limit: Option<usize>,
 }
 
+impl RestResponse {
+    /// Deserializes `result`, or returns `Error::Api` when `ret_code` is not `OK`.
+    pub fn into_result<T: DeserializeOwned>(self) -> Result<T, Error> {
+        match ReturnCode::from(self.ret_code) {
+            ReturnCode::OK => Ok(serde_json::from_value(self.result)?),
+            _ => Err(Error::Api {
+                ret_code: self.ret_code,
+                ret_msg: self.ret_msg,
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ApiKeyInfo {
+    pub api_key: String,
+    #[serde(rename(deserialize = "type"))]
+    pub key_type: String,
+    pub user_id: usize,
+    pub inviter_id: usize,
+    pub ips: Vec<String>,
+    pub note: String,
+    pub permissions: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_rfc3339")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "deserialize_optional_rfc3339")]
+    pub expired_at: Option<DateTime<Utc>>,
+    pub read_only: bool,
+}
+
+impl ApiKeyInfo {
+    pub fn is_expired(&self) -> bool {
+        matches!(self.expired_at, Some(expired_at) if expired_at <= Utc::now())
+    }
+
+    /// Keys bound to no IP address are accepted from anywhere.
+    pub fn is_ip_restricted(&self) -> bool {
+        !self.ips.is_empty() && !self.ips.iter().any(|ip| ip == "*")
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LcpInfo {
+    pub date: String,
+    pub self_ratio: f64,
+    pub platform_ratio: f64,
+    pub score: f64,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct LcpList {
+    pub lcp_list: Vec<LcpInfo>,
+}
+
+fn deserialize_optional_rfc3339<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // Keys that never expire come with no date or the epoch.
+    let s = match Option::<String>::deserialize(deserializer)? {
+        Some(s) if !s.is_empty() => s,
+        _ => return Ok(None),
+    };
+    let dt = DateTime::parse_from_rfc3339(&s).map_err(serde::de::Error::custom)?;
+
+    Ok(Some(dt.with_timezone(&Utc)).filter(|dt| dt.timestamp() > 0))
+}
+
 fn deserialize_time_now<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
     let timestamp = s.parse::<f64>().unwrap();
-    Ok(Utc.timestamp_millis((timestamp * 10f64.powi(3)) as i64))
+    Ok(Utc
+        .timestamp_millis_opt((timestamp * 10f64.powi(3)) as i64)
+        .unwrap())
 }
@@ -0,0 +1,45 @@
+//! Glue for the async runtime selected by cargo features.
+//!
+//! `tokio-runtime` (default) and `async-std-runtime` are mutually exclusive in
+//! spirit; if both are enabled, tokio wins.
+
+#[cfg(not(any(feature = "tokio-runtime", feature = "async-std-runtime")))]
+compile_error!("either the `tokio-runtime` or the `async-std-runtime` feature must be enabled");
+
+#[cfg(feature = "tokio-runtime")]
+mod imp {
+    pub(crate) use async_tungstenite::tokio::{connect_async, ConnectStream};
+    use std::future::Future;
+    use std::time::Duration;
+
+    pub(crate) fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        tokio::spawn(future);
+    }
+
+    pub(crate) async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+#[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
+mod imp {
+    pub(crate) use async_tungstenite::async_std::{connect_async, ConnectStream};
+    use std::future::Future;
+    use std::time::Duration;
+
+    pub(crate) fn spawn<F>(future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        async_std::task::spawn(future);
+    }
+
+    pub(crate) async fn sleep(duration: Duration) {
+        async_std::task::sleep(duration).await
+    }
+}
+
+pub(crate) use self::imp::*;
@@ -0,0 +1,133 @@
+//! Opt-in hooks for inspecting the traffic of [`Rest`](crate::rest::Rest) and
+//! [`WebSocket`](crate::websocket::WebSocket).
+//!
+//! Credentials never reach a hook: `api_key` and `sign` query parameters, and the
+//! arguments of websocket `auth` frames, are replaced with `[REDACTED]`.
+
+use chrono::{DateTime, Utc};
+use log::debug;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub(crate) const REDACTED: &str = "[REDACTED]";
+const SENSITIVE_PARAMS: [&str; 2] = ["api_key", "sign"];
+
+#[derive(Clone, Debug, Default)]
+pub struct RateLimit {
+    pub limit: Option<usize>,
+    pub status: Option<usize>,
+    pub reset_ms: Option<u64>,
+}
+
+/// One REST round trip.
+#[derive(Clone, Debug)]
+pub struct RestRecord {
+    pub method: String,
+    pub path: String,
+    pub params: BTreeMap<String, String>,
+    pub status: Option<u16>,
+    pub ret_code: Option<usize>,
+    pub ret_msg: Option<String>,
+    pub latency: Duration,
+    pub rate_limit: RateLimit,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// One websocket frame.
+#[derive(Clone, Debug)]
+pub struct FrameRecord {
+    pub direction: Direction,
+    pub timestamp: DateTime<Utc>,
+    pub payload: String,
+}
+
+pub trait RestHook: Send + Sync {
+    fn on_rest(&self, record: &RestRecord);
+}
+
+pub trait FrameHook: Send + Sync {
+    fn on_frame(&self, record: &FrameRecord);
+}
+
+/// Writes every record to the `log` crate at debug level.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogHook;
+
+impl RestHook for LogHook {
+    fn on_rest(&self, record: &RestRecord) {
+        debug!("{:?}", record);
+    }
+}
+
+impl FrameHook for LogHook {
+    fn on_frame(&self, record: &FrameRecord) {
+        debug!("{:?} {}", record.direction, record.payload);
+    }
+}
+
+pub(crate) fn redact_params<'a, I>(params: I) -> BTreeMap<String, String>
+where
+    I: IntoIterator<Item = (std::borrow::Cow<'a, str>, std::borrow::Cow<'a, str>)>,
+{
+    params
+        .into_iter()
+        .map(|(k, v)| {
+            if SENSITIVE_PARAMS.contains(&k.as_ref()) {
+                (k.into_owned(), REDACTED.to_owned())
+            } else {
+                (k.into_owned(), v.into_owned())
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn redact_frame(payload: &str) -> String {
+    match serde_json::from_str::<Value>(payload) {
+        Ok(mut frame) if frame["op"] == "auth" => {
+            if let Some(args) = frame["args"].as_array_mut() {
+                args.iter_mut()
+                    .for_each(|arg| *arg = Value::String(REDACTED.to_owned()));
+            }
+            frame.to_string()
+        }
+        _ => payload.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redact_frame, redact_params, REDACTED};
+    use reqwest::Url;
+
+    #[test]
+    fn test_redact_params() {
+        let uri = Url::parse(
+            "https://api-testnet.bybit.com/v2/private/order/list?api_key=key&sign=abc&symbol=BTCUSD",
+        )
+        .unwrap();
+        let params = redact_params(uri.query_pairs());
+
+        assert_eq!(params["api_key"], REDACTED);
+        assert_eq!(params["sign"], REDACTED);
+        assert_eq!(params["symbol"], "BTCUSD");
+    }
+
+    #[test]
+    fn test_redact_frame() {
+        let auth = r#"{"op":"auth","args":["key","1609459210000","signature"]}"#;
+        let redacted = redact_frame(auth);
+
+        assert!(!redacted.contains("key\""));
+        assert!(!redacted.contains("signature"));
+
+        let subscribe = r#"{"op":"subscribe","args":["trade.BTCUSD"]}"#;
+        assert_eq!(redact_frame(subscribe), subscribe);
+    }
+}
@@ -1,8 +1,29 @@
 mod core;
 mod enums;
+mod event;
+mod handle;
+mod heartbeat;
+mod reconnect;
+mod recorder;
+mod sequence;
+mod spot;
 pub mod store;
+mod stream;
 mod structs;
+mod subscription;
 
 pub use self::core::{WebSocket, WebSocketBuilder, WebSocketResponse};
-pub use self::enums::Topic;
-pub use self::structs::{Limit, OrderBook, Record};
+pub use self::enums::{Channel, Frequency, OrderBookDepth, Status, Tick, Topic, Trigger};
+pub use self::event::WsEvent;
+pub use self::handle::{ExitReason, WebSocketHandle};
+pub use self::heartbeat::HeartbeatPolicy;
+pub use self::reconnect::ReconnectPolicy;
+pub use self::recorder::Recorder;
+pub use self::stream::{EventStream, WebSocketWriter};
+pub use self::structs::{
+    Candle, CandleSeries, Execution, Instrument, InstrumentUpdate, Insurance, Limit, Liquidation,
+    Order, OrderBook, Position, Record, StopOrder, Ticker, Wallet,
+};
+pub use self::subscription::{
+    AckStatus, ActiveSubscription, SubscribeResult, Subscription, SubscriptionRegistry, Symbols,
+};
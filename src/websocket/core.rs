@@ -1,42 +1,79 @@
-use super::enums::Topic;
-use super::structs::WsArgs;
+use super::enums::{Channel, OrderBookDepth, Topic};
+use super::event::WsEvent;
+use super::handle::{ExitReason, WebSocketHandle};
+use super::heartbeat::{self, HeartbeatPolicy, StaleWatch};
+use super::reconnect::ReconnectPolicy;
+use super::recorder::{Frame, Recorder, RecorderHandle};
+use super::sequence::{Sequencer, Verdict};
+use super::spot;
+use super::store;
+use super::stream::{self, EventStream, WebSocketWriter};
+use super::structs::{deserialize_option_string_to_u64, WsArgs};
+use super::subscription::{AckStatus, SubscribeResult, Subscription, SubscriptionRegistry};
 use crate::common::{Endpoint, Symbol, API};
+use crate::runtime::{self, connect_async, ConnectStream};
+use crate::trace::{self, Direction, FrameHook, FrameRecord};
 
 use async_tungstenite::{
-    async_std::{connect_async, ConnectStream},
     tungstenite::{
         http::{Response, StatusCode},
+        protocol::Message,
         Error, Result,
     },
     WebSocketStream,
 };
 
 use chrono::{DateTime, Utc};
-use futures::{SinkExt, StreamExt};
-use hmac::{Hmac, Mac, NewMac};
-use log::{debug, error, info};
+use futures::{channel::oneshot, pin_mut, select, FutureExt, SinkExt, StreamExt};
+use log::{debug, error, info, warn};
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sha2::Sha256;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-type HmacSha256 = Hmac<Sha256>;
 type WSConnection = WebSocketStream<ConnectStream>;
 
 pub struct WebSocket {
     endpoint: Endpoint,
+    channel: Channel,
     api: API,
     ws_stream: WSConnection,
+    connection: u64,
+    hooks: Vec<Arc<dyn FrameHook>>,
+    recorder: Option<RecorderHandle>,
+    reconnect: ReconnectPolicy,
+    authenticated: bool,
+    registry: SubscriptionRegistry,
+    heartbeat: HeartbeatPolicy,
+    last_ping: Option<Instant>,
+    awaiting_pong: Option<Instant>,
+    stale_watches: Vec<StaleWatch>,
+    sequencer: Sequencer,
+    ack_timeout: Duration,
+    pending: VecDeque<WsEvent>,
+    outbox: VecDeque<WsArgs>,
+    closed: bool,
 }
 
 pub struct WebSocketBuilder<EndpointType, ApiType, WsType> {
     endpoint: EndpointType,
+    channel: Channel,
     api: ApiType,
     ws_stream: WsType,
+    hooks: Vec<Arc<dyn FrameHook>>,
+    recorder: Option<Recorder>,
+    reconnect: ReconnectPolicy,
+    ack_timeout: Duration,
+    heartbeat: HeartbeatPolicy,
+    stale_watches: Vec<StaleWatch>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct WebSocketResponse {
     pub topic: String,
     #[serde(default)]
@@ -48,6 +85,10 @@ pub struct WebSocketResponse {
     #[serde(with = "serde_option_timestamp")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_option_string_to_u64")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cross_seq: Option<u64>,
     pub data: Value,
 }
 
@@ -55,32 +96,68 @@ impl WebSocketBuilder<(), (), ()> {
     pub fn new() -> Self {
         WebSocketBuilder {
             endpoint: (),
+            channel: Channel::default(),
             api: (),
             ws_stream: (),
+            hooks: Vec::new(),
+            recorder: None,
+            reconnect: ReconnectPolicy::default(),
+            ack_timeout: Duration::from_secs(10),
+            heartbeat: HeartbeatPolicy::default(),
+            stale_watches: Vec::new(),
         }
     }
 }
 
+impl Default for WebSocketBuilder<(), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<WsType> WebSocketBuilder<Endpoint, API, WsType> {
     pub async fn build(self) -> WebSocket {
+        let ws_stream = self
+            .connect()
+            .await
+            .expect("Failed to connect Websocket API");
+        self.with_stream(ws_stream)
+    }
+
+    fn with_stream(self, ws_stream: WSConnection) -> WebSocket {
+        let channel = self.channel;
+        let registry = SubscriptionRegistry::default();
+        let recorder = self.recorder.as_ref().and_then(|recorder| {
+            recorder
+                .spawn(channel, registry.clone())
+                .map_err(|err| warn!("Failed to start recording: {}", err))
+                .ok()
+        });
         WebSocket {
             endpoint: self.endpoint.clone(),
+            channel,
             api: self.api.clone(),
-            ws_stream: self
-                .connect()
-                .await
-                .expect("Failed to connect Websocket API"),
+            ws_stream,
+            connection: next_connection(),
+            hooks: self.hooks,
+            recorder,
+            reconnect: self.reconnect,
+            authenticated: false,
+            registry,
+            heartbeat: self.heartbeat,
+            last_ping: None,
+            awaiting_pong: None,
+            stale_watches: self.stale_watches,
+            sequencer: Sequencer::default(),
+            ack_timeout: self.ack_timeout,
+            pending: VecDeque::new(),
+            outbox: VecDeque::new(),
+            closed: false,
         }
     }
 
-    async fn connect(self) -> Result<WSConnection> {
-        let url: Url = match self.endpoint {
-            Endpoint::MAINNET => Url::parse("wss://stream.bybit.com/realtime").unwrap(),
-            // Url::parse("wss://ws_stream.bytick.com/realtime").unwrap();
-            Endpoint::TESTNET => Url::parse("wss://stream-testnet.bybit.com/realtime").unwrap(),
-        };
-
-        let (ws_stream, _) = connect_async(url).await?;
+    async fn connect(&self) -> Result<WSConnection> {
+        let (ws_stream, _) = connect_async(url(&self.endpoint, self.channel)).await?;
         info!("Connected to websocket API");
 
         Ok(ws_stream)
@@ -91,67 +168,253 @@ impl<EndpointType, ApiType, WsType> WebSocketBuilder<EndpointType, ApiType, WsTy
     pub fn endpoint(self, endpoint: Endpoint) -> WebSocketBuilder<Endpoint, ApiType, WsType> {
         WebSocketBuilder {
             endpoint,
+            channel: self.channel,
             api: self.api,
             ws_stream: self.ws_stream,
+            hooks: self.hooks,
+            recorder: self.recorder,
+            reconnect: self.reconnect,
+            ack_timeout: self.ack_timeout,
+            heartbeat: self.heartbeat,
+            stale_watches: self.stale_watches,
         }
     }
 
     pub fn api(self, api: API) -> WebSocketBuilder<EndpointType, API, WsType> {
         WebSocketBuilder {
             endpoint: self.endpoint,
+            channel: self.channel,
             api,
             ws_stream: self.ws_stream,
+            hooks: self.hooks,
+            recorder: self.recorder,
+            reconnect: self.reconnect,
+            ack_timeout: self.ack_timeout,
+            heartbeat: self.heartbeat,
+            stale_watches: self.stale_watches,
         }
     }
+
+    /// The inverse `realtime` websocket by default.
+    pub fn channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// How long [`WebSocket::subscribe_to`] waits for an ack, 10 seconds by default.
+    pub fn ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    pub fn heartbeat(mut self, policy: HeartbeatPolicy) -> Self {
+        self.heartbeat = policy;
+        self
+    }
+
+    /// Raises [`WsEvent::Stale`] when `topic` sends nothing for `symbol` (`*` for
+    /// any symbol) within `after`, and [`WsEvent::Recovered`] once it resumes.
+    pub fn stale_after(mut self, topic: Topic, symbol: &str, after: Duration) -> Self {
+        self.stale_watches
+            .push(StaleWatch::new(topic, symbol, after));
+        self
+    }
+
+    /// Writes every frame to disk, see [`Recorder`].
+    pub fn recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn hook<H>(mut self, hook: H) -> Self
+    where
+        H: FrameHook + 'static,
+    {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+}
+
+fn subscriptions_for<T>(topics: T, symbol: Symbol) -> Vec<Subscription>
+where
+    T: IntoIterator<Item = Topic>,
+{
+    let symbol = symbol.to_string();
+    topics
+        .into_iter()
+        .map(|t| match t {
+            Topic::Insurance => Subscription::new(t),
+            t if t.is_public() => Subscription::new(t).symbol(symbol.as_str()),
+            t => Subscription::new(t),
+        })
+        .collect()
+}
+
+fn collect_args<S>(subscriptions: S, channel: Channel) -> Vec<String>
+where
+    S: IntoIterator<Item = Subscription>,
+{
+    let mut args: Vec<String> = Vec::new();
+    subscriptions
+        .into_iter()
+        .flat_map(|subscription| subscription.args(channel))
+        .for_each(|arg| {
+            if !args.contains(&arg) {
+                args.push(arg);
+            }
+        });
+    args
+}
+
+fn url(endpoint: &Endpoint, channel: Channel) -> Url {
+    let host = match endpoint {
+        Endpoint::MAINNET => "wss://stream.bybit.com",
+        // "wss://ws_stream.bytick.com"
+        Endpoint::TESTNET => "wss://stream-testnet.bybit.com",
+    };
+    Url::parse(&format!("{}/{}", host, channel.path())).unwrap()
 }
 
 impl WebSocket {
     pub async fn connect(&mut self) -> Result<()> {
-        let url: Url = match self.endpoint {
-            Endpoint::MAINNET => Url::parse("wss://stream.bybit.com/realtime").unwrap(),
-            // Url::parse("wss://ws_stream.bytick.com/realtime").unwrap();
-            Endpoint::TESTNET => Url::parse("wss://stream-testnet.bybit.com/realtime").unwrap(),
-        };
-
-        let (ws_stream, _) = connect_async(url).await?;
+        let (ws_stream, _) = connect_async(url(&self.endpoint, self.channel)).await?;
         info!("Connected to websocket");
 
         self.ws_stream = ws_stream;
+        self.connection = next_connection();
+
+        Ok(())
+    }
+
+    /// Reconnects with backoff, then re-authenticates and replays every subscription
+    /// so that the store is rebuilt from fresh snapshots.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let mut delays = self.reconnect.delays();
+
+        loop {
+            let err = match self.resume().await {
+                Ok(()) => {
+                    info!("Reconnected to websocket");
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+
+            match delays.next() {
+                Some(delay) => {
+                    warn!("Failed to reconnect: {}, retrying in {:?}", err, delay);
+                    runtime::sleep(delay).await;
+                }
+                None => return Err(err),
+            }
+        }
+    }
+
+    async fn resume(&mut self) -> Result<()> {
+        self.connect().await?;
+        self.sequencer.reset();
+        self.outbox.clear();
+        if self.authenticated {
+            self.authenticate().await?;
+        }
+        self.registry.reset();
+        let args = self.registry.args();
+        if !args.is_empty() {
+            let subscribe = WsArgs {
+                op: "subscribe".to_owned(),
+                args: Some(args),
+            };
+            self.send(subscribe).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send(&mut self, args: WsArgs) -> Result<()> {
+        let msgs = match self.channel.is_spot() {
+            true => spot::messages(args),
+            false => vec![args.into_msg()],
+        };
+        for msg in msgs {
+            self.record(Direction::Outbound, &msg);
+            self.ws_stream.send(msg).await?;
+        }
 
         Ok(())
     }
 
+    async fn receive(&mut self) -> Option<Result<Message>> {
+        let msg = self.ws_stream.next().await;
+        if let Some(Ok(msg)) = &msg {
+            self.record(Direction::Inbound, msg);
+        }
+        msg
+    }
+
+    fn record(&mut self, direction: Direction, msg: &Message) {
+        if self.hooks.is_empty() && self.recorder.is_none() {
+            return;
+        }
+
+        let record = FrameRecord {
+            direction,
+            timestamp: Utc::now(),
+            payload: match msg {
+                Message::Text(text) => trace::redact_frame(text),
+                Message::Binary(bytes) => format!("<binary {} bytes>", bytes.len()),
+                Message::Ping(_) => "<ping>".to_owned(),
+                Message::Pong(_) => "<pong>".to_owned(),
+                Message::Close(frame) => format!("<close {:?}>", frame),
+            },
+        };
+        self.hooks.iter().for_each(|hook| hook.on_frame(&record));
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Frame::new(
+                record.timestamp,
+                self.connection,
+                direction,
+                msg,
+            ));
+        }
+    }
+
     pub async fn authenticate(&mut self) -> Result<()> {
         let now = SystemTime::now();
         let unix_time = now.duration_since(UNIX_EPOCH).expect("back to the future");
         let expires = (unix_time.as_secs() + 10) * 1000;
 
-        let mut mac = HmacSha256::new_varkey(self.api.secret.as_bytes()).unwrap();
-        mac.update(b"GET/realtime");
-        mac.update(expires.to_string().as_bytes());
-        let signature = format!("{:x}", mac.finalize().into_bytes());
+        let signature = self
+            .api
+            .secret
+            .sign(format!("GET/realtime{}", expires).as_bytes());
 
         let auth = WsArgs {
             op: "auth".to_owned(),
             args: Some([self.api.key.clone(), expires.to_string(), signature].to_vec()),
         };
-        debug!("{}", serde_json::to_string(&auth).unwrap());
-
-        self.ws_stream.send(auth.into_msg()).await?;
+        self.send(auth).await?;
         info!("Sent authentication message");
 
-        match self.ws_stream.next().await {
+        match self.receive().await {
             Some(msg) => {
-                let msg = msg?;
-                let msg_json: Value = serde_json::from_str(&msg.into_text().unwrap()).unwrap();
+                let msg_json: Value =
+                    serde_json::from_str(&msg?.into_text()?).map_err(unprocessable)?;
                 debug!("{:?}", &msg_json);
 
-                match msg_json["success"] {
-                    Value::Bool(true) => {
+                // Spot replies with `"auth": "success"` instead.
+                match msg_json["success"] == Value::Bool(true) || msg_json["auth"] == "success" {
+                    true => {
                         info!("Authentication successful");
+                        self.authenticated = true;
                         Ok(())
                     }
-                    _ => {
+                    false => {
                         error!("Websocket Authentication Failed");
                         Err(Error::Http(
                             Response::builder()
@@ -171,31 +434,151 @@ impl WebSocket {
         }
     }
 
-    pub async fn subscribe<T>(&mut self, topics: T, symbol: Symbol) -> Result<()>
+    /// Subscribes every topic to `symbol`; account-wide topics ignore it.
+    pub async fn subscribe<T>(&mut self, topics: T, symbol: Symbol) -> Result<SubscribeResult>
     where
         T: IntoIterator<Item = Topic>,
     {
-        let subscribe = WsArgs {
-            op: "subscribe".to_owned(),
-            args: Some(
-                topics
-                    .into_iter()
-                    .map(|t| match t {
-                        Topic::OrderBook25
-                        | Topic::OrderBook200
-                        | Topic::Trade
-                        | Topic::Instrument
-                        | Topic::KLine => format!("{}.{}", t.into_string(), symbol.to_string()),
-                        _ => t.into_string(),
-                    })
-                    .collect(),
-            ),
+        self.subscribe_to(subscriptions_for(topics, symbol)).await
+    }
+
+    /// Subscribes to each topic with its own symbols and parameter in a single
+    /// `subscribe` op and waits for the ack.
+    ///
+    /// Bybit acks a `subscribe` op as a whole, so if it is rejected each argument is
+    /// retried on its own to tell which ones failed; those the batch did subscribe
+    /// are acked as `already subscribed`, which counts as success. Spot acks every
+    /// topic on its own, so there they are always sent one by one. Events received
+    /// meanwhile are kept for [`WebSocket::next_event`].
+    ///
+    /// If an ack doesn't arrive in time, the results so far are returned with the
+    /// remaining arguments failed; those stay registered and await their ack.
+    pub async fn subscribe_to<S>(&mut self, subscriptions: S) -> Result<SubscribeResult>
+    where
+        S: IntoIterator<Item = Subscription>,
+    {
+        let args = collect_args(subscriptions, self.channel);
+        if args.is_empty() {
+            return Ok(SubscribeResult::default());
+        }
+
+        self.registry.insert(&args);
+        self.acknowledge("subscribe", args).await
+    }
+
+    /// Unsubscribes every topic from `symbol`, see [`WebSocket::subscribe`].
+    pub async fn unsubscribe<T>(&mut self, topics: T, symbol: Symbol) -> Result<SubscribeResult>
+    where
+        T: IntoIterator<Item = Topic>,
+    {
+        self.unsubscribe_from(subscriptions_for(topics, symbol))
+            .await
+    }
+
+    /// Sends a single `unsubscribe` op and forgets the subscriptions it acks, like
+    /// [`WebSocket::subscribe_to`]; those a rejected batch did unsubscribe are acked
+    /// as `not subscribed` on retry, which counts as success. Its ack lets
+    /// [`Store`](super::store::Store) drop the data of the unsubscribed symbols.
+    pub async fn unsubscribe_from<S>(&mut self, subscriptions: S) -> Result<SubscribeResult>
+    where
+        S: IntoIterator<Item = Subscription>,
+    {
+        let args = collect_args(subscriptions, self.channel);
+        if args.is_empty() {
+            return Ok(SubscribeResult::default());
+        }
+
+        let result = self.acknowledge("unsubscribe", args).await?;
+        self.registry.remove(&result.succeeded);
+
+        Ok(result)
+    }
+
+    /// Sends `op` for every argument at once, then one by one if it is rejected,
+    /// see [`WebSocket::subscribe_to`].
+    async fn acknowledge(&mut self, op: &str, args: Vec<String>) -> Result<SubscribeResult> {
+        let mut result = SubscribeResult::default();
+        if args.len() > 1 && !self.channel.is_spot() {
+            match self.request(op, args.clone()).await? {
+                Some((true, _)) => {
+                    result.succeeded = args;
+                    return Ok(result);
+                }
+                Some((false, _)) => {}
+                None => {
+                    result.failed = timed_out(args, self.ack_timeout);
+                    return Ok(result);
+                }
+            }
+        }
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match self.request(op, vec![arg.clone()]).await? {
+                Some((true, _)) => result.succeeded.push(arg),
+                Some((false, ret_msg)) => result.failed.push((arg, ret_msg)),
+                None => {
+                    let rest = std::iter::once(arg).chain(args).collect();
+                    result.failed.extend(timed_out(rest, self.ack_timeout));
+                    break;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Sends `op` and reads until its ack, matched on the request args, arrives.
+    /// Returns `None` if it doesn't within the ack timeout.
+    async fn request(&mut self, op: &str, args: Vec<String>) -> Result<Option<(bool, String)>> {
+        let request = WsArgs {
+            op: op.to_owned(),
+            args: Some(args.clone()),
         };
+        self.flush().await?;
+        self.send(request).await?;
+        info!("Sent {} message", op);
 
-        self.ws_stream.send(subscribe.into_msg()).await?;
-        info!("Sent subscribe message");
+        let deadline = runtime::sleep(self.ack_timeout).fuse();
+        pin_mut!(deadline);
 
-        Ok(())
+        loop {
+            let event = select! {
+                event = self.read_event().fuse() => event?,
+                _ = deadline => {
+                    warn!("No ack for {} {:?}", op, args);
+                    return Ok(None);
+                }
+            };
+            // Resyncs queued by the event go out before the ack arrives.
+            self.flush().await?;
+
+            match event {
+                Some(WsEvent::Ack {
+                    success,
+                    ret_msg,
+                    op: ack_op,
+                    args: ack_args,
+                }) if ack_op == op && ack_args == args => {
+                    let success = success || is_already_done(op, &ret_msg);
+                    self.pending.push_back(WsEvent::Ack {
+                        success,
+                        ret_msg: ret_msg.clone(),
+                        op: ack_op,
+                        args: ack_args,
+                    });
+                    return Ok(Some((success, ret_msg)));
+                }
+                Some(event) => self.pending.push_back(event),
+                None => {}
+            }
+        }
+    }
+
+    /// A handle on the active subscriptions, which stays valid after
+    /// [`WebSocket::into_stream`].
+    pub fn registry(&self) -> SubscriptionRegistry {
+        self.registry.clone()
     }
 
     pub async fn ping(&mut self) -> Result<()> {
@@ -204,7 +587,7 @@ impl WebSocket {
             args: None,
         };
 
-        self.ws_stream.send(ping.into_msg()).await?;
+        self.send(ping).await?;
         debug!("Sent ping");
 
         Ok(())
@@ -239,63 +622,374 @@ impl WebSocket {
         // }
     }
 
-    pub async fn on_message(&mut self) -> Result<()> {
-        if let Some(msg) = self.ws_stream.next().await {
-            let msg = msg?;
-
-            let msg_json: WebSocketResponse =
-                match serde_json::from_str::<WebSocketResponse>(msg.to_text().unwrap()) {
-                    Ok(res) => res,
-                    Err(_) => {
-                        if let Ok(res) = serde_json::from_str::<Value>(msg.to_text().unwrap()) {
-                            if res["success"] == Value::Bool(true) {
-                                if res["ret_msg"] == "pong" {
-                                    debug!("Ping successful");
-                                } else {
-                                    info!("Subscription successful");
-                                }
-                            }
-                            return Ok(());
-                        } else {
-                            error!("Subscription Failed: the subscribed topics may are invalid");
-                            return Err(Error::Http(
-                                Response::builder()
-                                    .body(Some("Subscription Failed".to_owned()))
-                                    .unwrap(),
-                            ));
-                        }
+    /// Reads frames until the next event, pinging on a timer and reconnecting
+    /// transparently when the connection drops or a pong is overdue. Returns `None`
+    /// once reconnecting has been given up.
+    pub async fn next_event(&mut self) -> Option<Result<WsEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.closed {
+                return None;
+            }
+
+            let heartbeat = match self.flush().await {
+                Ok(()) => self.heartbeat().await,
+                Err(err) => Err(err),
+            };
+            let result = match heartbeat {
+                Ok(Some(deadline)) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    select! {
+                        result = self.read_event().fuse() => result,
+                        _ = runtime::sleep(timeout).fuse() => Ok(None),
                     }
+                }
+                Ok(None) => continue,
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => {}
+                Err(Error::Http(res)) => return Some(Err(Error::Http(res))),
+                Err(err) => {
+                    error!("Websocket disconnected: {}", err);
+                    self.last_ping = None;
+                    self.awaiting_pong = None;
+                    return match self.reconnect().await {
+                        Ok(()) => {
+                            heartbeat::restart(&mut self.stale_watches);
+                            Some(Ok(WsEvent::Reconnected))
+                        }
+                        Err(err) => {
+                            error!("Gave up reconnecting to websocket: {}", err);
+                            self.closed = true;
+                            Some(Err(err))
+                        }
+                    };
+                }
+            }
+        }
+    }
+
+    /// Sends a due ping, fails on an overdue pong and queues staleness events.
+    /// Returns when the next of these is due, or `None` if events were queued.
+    async fn heartbeat(&mut self) -> Result<Option<Instant>> {
+        let now = Instant::now();
+
+        if let Some(sent) = self.awaiting_pong {
+            if now >= sent + self.heartbeat.pong_timeout {
+                warn!("No pong within {:?}", self.heartbeat.pong_timeout);
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "pong timeout",
+                )));
+            }
+        }
+
+        let ping_at = self
+            .last_ping
+            .map_or(now, |last_ping| last_ping + self.heartbeat.ping_interval);
+        if now >= ping_at {
+            self.ping().await?;
+            self.last_ping = Some(now);
+            self.awaiting_pong.get_or_insert(now);
+        }
+
+        let stale = heartbeat::expire(&mut self.stale_watches, now);
+        if !stale.is_empty() {
+            stale
+                .into_iter()
+                .for_each(|event| self.pending.push_back(event));
+            return Ok(None);
+        }
+
+        let deadlines = [
+            self.last_ping.map(|p| p + self.heartbeat.ping_interval),
+            self.awaiting_pong.map(|p| p + self.heartbeat.pong_timeout),
+            heartbeat::next_deadline(&self.stale_watches),
+        ];
+        Ok(deadlines.iter().flatten().min().copied())
+    }
+
+    async fn read_event(&mut self) -> Result<Option<WsEvent>> {
+        let msg = self
+            .receive()
+            .await
+            .unwrap_or(Err(Error::ConnectionClosed))?;
+        let text = match msg.to_text() {
+            Ok(text) => text,
+            Err(_) => return Ok(None),
+        };
+
+        if self.channel.is_spot() {
+            let frame = spot::parse(text).map_err(unprocessable)?;
+            if let Some((topic, symbol)) = frame.topic {
+                let recovered = heartbeat::touch(&mut self.stale_watches, topic, symbol.as_deref());
+                self.pending.extend(recovered);
+            }
+            frame.events.iter().for_each(|event| self.observe(event));
+            let mut events = frame.events.into_iter();
+            let event = events.next();
+            self.pending.extend(events);
+            return Ok(event);
+        }
+
+        if let Ok(res) = serde_json::from_str::<WebSocketResponse>(text) {
+            if let Some((topic, symbol)) = Topic::parse(&res.topic) {
+                let recovered = heartbeat::touch(&mut self.stale_watches, topic, symbol.as_deref());
+                self.pending.extend(recovered);
+            }
+            let event = WsEvent::try_from(res).map_err(unprocessable)?;
+            return Ok(self.sequence(event));
+        }
+
+        match serde_json::from_str::<Value>(text) {
+            Ok(res) if res["ret_msg"] == "pong" => {
+                self.observe(&WsEvent::Pong);
+                Ok(Some(WsEvent::Pong))
+            }
+            Ok(res) => {
+                let ack = WsEvent::Ack {
+                    success: res["success"] == Value::Bool(true),
+                    ret_msg: res["ret_msg"].as_str().unwrap_or_default().to_owned(),
+                    op: res["request"]["op"].as_str().unwrap_or_default().to_owned(),
+                    args: serde_json::from_value(res["request"]["args"].clone())
+                        .unwrap_or_default(),
                 };
-            super::store::store_message(msg_json);
+                self.observe(&ack);
+                Ok(Some(ack))
+            }
+            Err(_) => {
+                error!("Subscription Failed: the subscribed topics may are invalid");
+                Err(Error::Http(
+                    Response::builder()
+                        .body(Some("Subscription Failed".to_owned()))
+                        .unwrap(),
+                ))
+            }
+        }
+    }
+
+    /// Drops orderbook deltas that don't follow their book and queues its
+    /// resubscription, see [`WsEvent::OrderBookGap`].
+    fn sequence(&mut self, event: WsEvent) -> Option<WsEvent> {
+        match self.sequencer.check(&event) {
+            Verdict::Apply => Some(event),
+            Verdict::Drop => None,
+            Verdict::Gap { last_seq } => match event {
+                WsEvent::OrderBookDelta {
+                    depth,
+                    symbol,
+                    cross_seq,
+                    ..
+                } => {
+                    warn!(
+                        "Orderbook {} of {} out of sequence ({:?} after {:?}), resyncing",
+                        Topic::OrderBook(depth),
+                        symbol,
+                        cross_seq,
+                        last_seq
+                    );
+                    self.resync(depth, &symbol);
+                    Some(WsEvent::OrderBookGap {
+                        depth,
+                        symbol,
+                        last_seq,
+                        cross_seq,
+                    })
+                }
+                event => Some(event),
+            },
+        }
+    }
+
+    /// Queues the resubscription of the `depth` orderbook of `symbol`, or of the
+    /// wildcard covering it, which makes Bybit send a new snapshot.
+    fn resync(&mut self, depth: OrderBookDepth, symbol: &str) {
+        let args: Vec<String> = self
+            .registry
+            .active()
+            .into_iter()
+            .filter(|s| s.topic == Topic::OrderBook(depth))
+            .filter(|s| matches!(s.symbol.as_deref(), Some(s) if s == symbol || s == "*"))
+            .map(|s| s.arg)
+            .collect();
+        if args.is_empty() {
+            return;
+        }
+
+        for op in &["unsubscribe", "subscribe"] {
+            self.outbox.push_back(WsArgs {
+                op: (*op).to_owned(),
+                args: Some(args.clone()),
+            });
+        }
+        self.registry.set_status(&args, AckStatus::Pending);
+    }
+
+    /// Sends the queued ops, from [`WebSocket::next_event`] and while a request
+    /// waits for its ack. Each leaves the queue once sent, so they survive the
+    /// cancellation of either.
+    async fn flush(&mut self) -> Result<()> {
+        while let Some(args) = self.outbox.front().cloned() {
+            self.send(args).await?;
+            self.outbox.pop_front();
         }
 
         Ok(())
     }
 
-    pub async fn run_forever(mut self) {
-        tokio::spawn(async move {
-            self.ping().await.expect("Ping Failed");
-            let mut now = SystemTime::now();
-
-            loop {
-                if let Ok(elapsed) = now.elapsed() {
-                    if elapsed.as_secs() >= 60 {
-                        self.ping().await.expect("Ping Failed");
-                        now = SystemTime::now();
-                    }
+    /// Clears the pending pong and records the outcome of subscriptions.
+    fn observe(&mut self, event: &WsEvent) {
+        match event {
+            WsEvent::Pong => {
+                debug!("Ping successful");
+                self.awaiting_pong = None;
+            }
+            WsEvent::Ack {
+                success,
+                ret_msg,
+                op,
+                args,
+            } => {
+                if *success {
+                    info!("Subscription successful");
+                } else {
+                    error!("Request failed: {}", ret_msg);
+                }
+                if op == "subscribe" {
+                    let status = if *success || is_already_done(op, ret_msg) {
+                        AckStatus::Acked
+                    } else {
+                        AckStatus::Rejected
+                    };
+                    self.registry.set_status(args, status);
                 }
-
-                self.on_message().await.unwrap();
             }
+            _ => {}
+        }
+    }
+
+    /// Reads the next event and applies it to the global store.
+    pub async fn on_message(&mut self) -> Result<()> {
+        self.flush().await?;
+        if let Some(event) = self.read_event().await? {
+            store::global().apply(&event);
+        }
+
+        Ok(())
+    }
+
+    /// Spawns a task reading events into the returned stream, with a writer to
+    /// keep subscribing, unsubscribing and closing. The task stops once the stream
+    /// is dropped or the socket is closed.
+    pub fn into_stream(self) -> (EventStream, WebSocketWriter) {
+        stream::split(self)
+    }
+
+    /// Spawns a task applying every event to the global store until the returned
+    /// handle closes it or reconnecting is given up.
+    pub async fn run_forever(mut self) -> WebSocketHandle {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (exit_tx, exit_rx) = oneshot::channel();
+
+        runtime::spawn(async move {
+            let mut shutdown_rx = shutdown_rx.fuse();
+            let reason = loop {
+                let event = select! {
+                    event = self.next_event().fuse() => event,
+                    shutdown = shutdown_rx => match shutdown {
+                        Ok(()) => {
+                            if let Err(err) = self.close().await {
+                                warn!("Failed to close websocket: {}", err);
+                            }
+                            break ExitReason::Shutdown;
+                        }
+                        // The handle was dropped, which detaches the task.
+                        Err(_) => continue,
+                    },
+                };
+
+                match event {
+                    Some(Ok(event)) => store::global().apply(&event),
+                    Some(Err(err)) if self.closed => break ExitReason::Disconnected(err),
+                    Some(Err(_)) => {}
+                    None => break ExitReason::Aborted,
+                }
+            };
+
+            info!("Websocket task stopped: {:?}", reason);
+            let _ = exit_tx.send(reason);
         });
-        // .await
-        // .unwrap();
+
+        WebSocketHandle::new(shutdown_tx, exit_rx)
+    }
+
+    /// Sends a Close frame, flushes and waits up to the ack timeout for the server
+    /// to close its side. Later calls to [`WebSocket::next_event`] return `None`.
+    pub async fn close(&mut self) -> Result<()> {
+        self.closed = true;
+        self.record(Direction::Outbound, &Message::Close(None));
+        self.ws_stream.close(None).await?;
+
+        let timeout = runtime::sleep(self.ack_timeout);
+        let drain = async {
+            while let Some(Ok(msg)) = self.ws_stream.next().await {
+                self.record(Direction::Inbound, &msg);
+            }
+        };
+        select! {
+            _ = drain.fuse() => {},
+            _ = timeout.fuse() => {},
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.finish();
+        }
+        info!("Closed websocket");
+
+        Ok(())
     }
 }
 
+/// Bybit rejects subscribing twice to a topic, which leaves it subscribed, and
+/// unsubscribing from a topic it isn't subscribed to, which leaves it unsubscribed.
+fn is_already_done(op: &str, ret_msg: &str) -> bool {
+    match op {
+        "subscribe" => ret_msg.contains("already subscribed"),
+        "unsubscribe" => ret_msg.contains("not subscribed"),
+        _ => false,
+    }
+}
+
+fn timed_out(args: Vec<String>, timeout: Duration) -> Vec<(String, String)> {
+    args.into_iter()
+        .map(|arg| (arg, format!("No ack within {:?}", timeout)))
+        .collect()
+}
+
+/// Tells apart the connections of every socket in the process, e.g. in recordings.
+fn next_connection() -> u64 {
+    static CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+    CONNECTIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+fn unprocessable(err: serde_json::Error) -> Error {
+    error!("Failed to parse websocket message: {}", err);
+    Error::Http(
+        Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Some(err.to_string()))
+            .unwrap(),
+    )
+}
+
 mod serde_option_timestamp {
+    use super::super::structs::deserialize_string_to_u64;
     use chrono::{DateTime, TimeZone, Utc};
-    use serde::{self, Deserialize, Deserializer, Serializer};
+    use serde::{self, Deserializer, Serializer};
 
     pub fn serialize<S>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -309,7 +1003,75 @@ mod serde_option_timestamp {
     where
         D: Deserializer<'de>,
     {
-        let s = i64::deserialize(deserializer)?;
+        let s = deserialize_string_to_u64(deserializer)? as i64;
         Ok(Some(Utc.timestamp_nanos(s * 10_i64.pow(3))))
     }
 }
+
+#[cfg(all(test, feature = "tokio-runtime"))]
+mod tests {
+    use super::{WebSocket, WebSocketBuilder};
+    use crate::common::{Endpoint, API};
+    use crate::websocket::{Subscription, Topic};
+    use async_tungstenite::tokio::{accept_async, connect_async};
+    use async_tungstenite::tungstenite::Message;
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    /// A socket on a local server acking each request with `(success, ret_msg)`.
+    async fn connect<F>(ack: F) -> WebSocket
+    where
+        F: Fn(&str, &[Value]) -> (bool, &'static str) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut server = accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = server.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                let op = request["op"].as_str().unwrap_or_default();
+                let args = request["args"].as_array().cloned().unwrap_or_default();
+                let (success, ret_msg) = ack(op, &args);
+                let reply = json!({
+                    "success": success,
+                    "ret_msg": ret_msg,
+                    "conn_id": "test",
+                    "request": request,
+                });
+                server.send(Message::text(reply.to_string())).await.unwrap();
+            }
+        });
+
+        let (ws_stream, _) = connect_async(format!("ws://{}", addr)).await.unwrap();
+        WebSocketBuilder::new()
+            .endpoint(Endpoint::TESTNET)
+            .api(API {
+                key: "key".to_owned(),
+                secret: "secret".into(),
+            })
+            .with_stream(ws_stream)
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_partially_applied_batch() {
+        // The batch unsubscribes BTCUSD, then fails.
+        let mut ws = connect(|op, args| match (op, args) {
+            ("subscribe", _) => (true, ""),
+            (_, [_, _, _]) => (false, "error:unsubscribe trade.ETHUSD failed"),
+            (_, [arg]) if arg == "trade.BTCUSD" => (false, "error:trade.BTCUSD not subscribed"),
+            (_, [arg]) if arg == "trade.ETHUSD" => (true, ""),
+            _ => (false, "error:handler not found"),
+        })
+        .await;
+        let trades = || Subscription::new(Topic::Trade).symbols(vec!["BTCUSD", "ETHUSD", "XRPUSD"]);
+
+        assert!(ws.subscribe_to(vec![trades()]).await.unwrap().is_ok());
+        let result = ws.unsubscribe_from(vec![trades()]).await.unwrap();
+
+        assert_eq!(result.succeeded, vec!["trade.BTCUSD", "trade.ETHUSD"]);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(ws.registry.args(), vec!["trade.XRPUSD"]);
+    }
+}
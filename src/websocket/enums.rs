@@ -1,67 +1,351 @@
-#[derive(Debug)]
+use crate::rest::Interval;
+use std::fmt;
+use std::str::FromStr;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug, Default)]
 pub enum Tick {
+    #[default]
     MinusTick,
     ZeroMinusTick,
     PlusTick,
     ZeroPlusTick,
 }
 
-impl Default for Tick {
+/// Which of Bybit's websockets to connect to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Channel {
+    /// `realtime`, public and private topics of inverse contracts.
+    #[default]
+    Inverse,
+    /// `realtime_public`, public topics of USDT perpetuals.
+    LinearPublic,
+    /// `realtime_private`, private topics of USDT perpetuals.
+    LinearPrivate,
+    /// `spot/quote/ws/v1`, public spot topics.
+    SpotPublic,
+    /// `spot/ws`, private spot streams, pushed without subscribing once
+    /// authenticated.
+    SpotPrivate,
+}
+
+impl Channel {
+    pub(crate) fn path(self) -> &'static str {
+        match self {
+            Channel::Inverse => "realtime",
+            Channel::LinearPublic => "realtime_public",
+            Channel::LinearPrivate => "realtime_private",
+            Channel::SpotPublic => "spot/quote/ws/v1",
+            Channel::SpotPrivate => "spot/ws",
+        }
+    }
+
+    pub fn is_spot(self) -> bool {
+        matches!(self, Channel::SpotPublic | Channel::SpotPrivate)
+    }
+}
+
+/// How often a batched topic pushes, in milliseconds: the `100ms` of
+/// `orderBook_200.100ms`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Frequency(pub u32);
+
+impl Default for Frequency {
     fn default() -> Self {
-        Tick::MinusTick
+        Frequency(100)
     }
 }
 
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}ms", self.0)
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_suffix("ms")
+            .and_then(|ms| ms.parse().ok())
+            .map(Frequency)
+            .ok_or_else(|| format!("Impossible frequency: {}", s))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OrderBookDepth {
+    /// `orderBookL2_25`, pushed on every change.
+    L25,
+    /// `orderBook_200`, pushed at the given frequency.
+    L200(Frequency),
+    /// Spot `depth`, the 40 best levels on each side.
+    Spot,
+    /// Spot `mergedDepth`, levels merged to this many decimals.
+    Merged(u8),
+}
+
+impl OrderBookDepth {
+    /// `orderBookL2_25` for a depth of 25, `orderBook_200` every 100ms for 200.
+    ///
+    /// # Panics
+    ///
+    /// If `depth` is neither 25 nor 200, the only depths Bybit offers.
+    pub fn new(depth: u16) -> Self {
+        match depth {
+            25 => OrderBookDepth::L25,
+            200 => OrderBookDepth::L200(Frequency::default()),
+            _ => panic!("Unsupported order book depth {}, expected 25 or 200", depth),
+        }
+    }
+}
+
+/// A topic with its parameters, which round-trips through the topics Bybit sends:
+/// `Topic::parse("klineV2.5.BTCUSD")` gives `Topic::KLine(Interval::FiveMin)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Topic {
-    OrderBook25,
-    OrderBook200,
+    OrderBook(OrderBookDepth),
     Trade,
     Insurance,
-    Instrument,
-    KLine,
+    Instrument(Frequency),
+    KLine(Interval),
+    Liquidation,
     Position,
     Execution,
     Order,
     StopOrder,
+    Wallet,
+    /// Spot only, `realtimes`, 24 hour statistics.
+    Ticker,
 }
 
 impl Topic {
+    /// See [`OrderBookDepth::new`].
+    pub fn orderbook(depth: u16) -> Self {
+        Topic::OrderBook(OrderBookDepth::new(depth))
+    }
+
+    /// The topic as subscribed to on the inverse websocket, without the symbol.
     pub fn into_string(self) -> String {
-        String::from(match self {
-            Topic::OrderBook25 => "orderBookL2_25",
-            Topic::OrderBook200 => "orderBook_200.100ms",
+        self.prefix_on(Channel::Inverse)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Topic::OrderBook(OrderBookDepth::L25) => "orderBookL2_25",
+            Topic::OrderBook(OrderBookDepth::L200(_)) => "orderBook_200",
+            Topic::OrderBook(OrderBookDepth::Spot) => "depth",
+            Topic::OrderBook(OrderBookDepth::Merged(_)) => "mergedDepth",
             Topic::Trade => "trade",
             Topic::Insurance => "insurance",
-            Topic::Instrument => "instrument_info.100ms",
-            Topic::KLine => "klineV2.1",
+            Topic::Instrument(_) => "instrument_info",
+            Topic::KLine(_) => "klineV2",
+            Topic::Liquidation => "liquidation",
             Topic::Position => "position",
             Topic::Execution => "execution",
             Topic::Order => "order",
             Topic::StopOrder => "stop_order",
-        })
+            Topic::Wallet => "wallet",
+            Topic::Ticker => "realtimes",
+        }
+    }
+
+    /// The name and parameter to subscribe with on `channel`, e.g. `klineV2.5`;
+    /// linear klines are `candle` and spot ones `kline` with a `klineType`.
+    pub(crate) fn prefix_on(self, channel: Channel) -> String {
+        let name = match (self, channel) {
+            (Topic::KLine(_), Channel::LinearPublic | Channel::LinearPrivate) => "candle",
+            (Topic::KLine(_), Channel::SpotPublic | Channel::SpotPrivate) => "kline",
+            _ => self.name(),
+        };
+        let param = match self {
+            Topic::OrderBook(OrderBookDepth::L200(frequency)) | Topic::Instrument(frequency) => {
+                Some(frequency.to_string())
+            }
+            Topic::KLine(interval) if channel.is_spot() => Some(kline_type(interval).to_owned()),
+            Topic::KLine(interval) => Some(interval.to_string()),
+            Topic::OrderBook(OrderBookDepth::Merged(scale)) => Some(scale.to_string()),
+            _ => None,
+        };
+
+        match param {
+            Some(param) => format!("{}.{}", name, param),
+            None => name.to_owned(),
+        }
+    }
+
+    /// Public topics are per symbol (per currency for insurance); private ones
+    /// cover the whole account.
+    pub fn is_public(self) -> bool {
+        matches!(
+            self,
+            Topic::OrderBook(_)
+                | Topic::Trade
+                | Topic::Insurance
+                | Topic::Instrument(_)
+                | Topic::KLine(_)
+                | Topic::Liquidation
+                | Topic::Ticker
+        )
+    }
+
+    /// Splits a topic as sent by Bybit, e.g. `orderBook_200.100ms.BTCUSD` or
+    /// `insurance.BTC`, into the topic and its symbol (currency for insurance).
+    pub fn parse(topic: &str) -> Option<(Topic, Option<String>)> {
+        let parts: Vec<&str> = topic.split('.').collect();
+        let param = || parts.get(1).copied().unwrap_or_default();
+        let topic = match parts[0] {
+            "orderBookL2_25" => Topic::OrderBook(OrderBookDepth::L25),
+            "orderBook_200" => Topic::OrderBook(OrderBookDepth::L200(param().parse().ok()?)),
+            "trade" => Topic::Trade,
+            "insurance" => Topic::Insurance,
+            "instrument_info" => Topic::Instrument(param().parse().ok()?),
+            "klineV2" | "candle" | "kline" => Topic::KLine(param().parse().ok()?),
+            "liquidation" => Topic::Liquidation,
+            "position" => Topic::Position,
+            "execution" => Topic::Execution,
+            "order" => Topic::Order,
+            "stop_order" => Topic::StopOrder,
+            "wallet" => Topic::Wallet,
+            "depth" => Topic::OrderBook(OrderBookDepth::Spot),
+            "mergedDepth" => Topic::OrderBook(OrderBookDepth::Merged(param().parse().ok()?)),
+            "realtimes" => Topic::Ticker,
+            _ => return None,
+        };
+        let params = match topic {
+            Topic::OrderBook(OrderBookDepth::L200(_))
+            | Topic::Instrument(_)
+            | Topic::KLine(_)
+            | Topic::OrderBook(OrderBookDepth::Merged(_)) => 1,
+            _ => 0,
+        };
+
+        Some((topic, parts.get(1 + params).map(|s| s.to_string())))
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.into_string())
+    }
+}
+
+/// The spot `klineType` of `interval`.
+pub(crate) fn kline_type(interval: Interval) -> &'static str {
+    match interval {
+        Interval::OneMin => "1m",
+        Interval::ThreeMin => "3m",
+        Interval::FiveMin => "5m",
+        Interval::FifteenMin => "15m",
+        Interval::ThirtyMin => "30m",
+        Interval::OneHour => "1h",
+        Interval::TwoHour => "2h",
+        Interval::FourHour => "4h",
+        Interval::SixHour => "6h",
+        Interval::TwelveHour => "12h",
+        Interval::Day => "1d",
+        Interval::Week => "1w",
+        Interval::Month => "1M",
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Trigger {
     LastPrice,
     IndexPrice,
+    MarkPrice,
+    #[default]
     None,
 }
 
-impl Default for Trigger {
-    fn default() -> Self {
-        Trigger::None
-    }
-}
-
+#[derive(Clone, Debug, Default)]
 pub enum Status {
+    #[default]
     Normal,
     Liquidation,
     AutoDeleveraging,
 }
 
-impl Default for Status {
-    fn default() -> Self {
-        Status::Normal
+#[cfg(test)]
+mod tests {
+    use super::{Frequency, OrderBookDepth, Topic};
+    use crate::rest::Interval;
+
+    #[test]
+    fn test_orderbook_depth() {
+        assert_eq!(OrderBookDepth::new(25), OrderBookDepth::L25);
+        assert_eq!(
+            OrderBookDepth::new(200),
+            OrderBookDepth::L200(Frequency::default())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported order book depth 50")]
+    fn test_orderbook_depth_rejected() {
+        OrderBookDepth::new(50);
+    }
+
+    #[test]
+    fn test_parse_topic() {
+        assert_eq!(
+            Topic::parse("orderBookL2_25.BTCUSD"),
+            Some((
+                Topic::OrderBook(OrderBookDepth::L25),
+                Some("BTCUSD".to_owned())
+            ))
+        );
+        assert_eq!(
+            Topic::parse("orderBook_200.100ms.ETHUSD"),
+            Some((
+                Topic::OrderBook(OrderBookDepth::L200(Frequency(100))),
+                Some("ETHUSD".to_owned())
+            ))
+        );
+        assert_eq!(
+            Topic::parse("klineV2.1.BTCUSD"),
+            Some((Topic::KLine(Interval::OneMin), Some("BTCUSD".to_owned())))
+        );
+        assert_eq!(
+            Topic::parse("insurance.BTC"),
+            Some((Topic::Insurance, Some("BTC".to_owned())))
+        );
+        assert_eq!(Topic::parse("wallet"), Some((Topic::Wallet, None)));
+        assert_eq!(
+            Topic::parse("kline.1m.BTCUSDT"),
+            Some((Topic::KLine(Interval::OneMin), Some("BTCUSDT".to_owned())))
+        );
+        assert_eq!(
+            Topic::parse("mergedDepth.1.BTCUSDT"),
+            Some((
+                Topic::OrderBook(OrderBookDepth::Merged(1)),
+                Some("BTCUSDT".to_owned())
+            ))
+        );
+        assert_eq!(Topic::parse("klineV2.7.BTCUSD"), None);
+        assert_eq!(Topic::parse("unknown.BTCUSD"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let topics = [
+            Topic::orderbook(25),
+            Topic::orderbook(200),
+            Topic::Instrument(Frequency(100)),
+            Topic::KLine(Interval::Day),
+            Topic::KLine(Interval::FourHour),
+            Topic::Trade,
+            Topic::Liquidation,
+            Topic::Position,
+        ];
+
+        for topic in topics.iter() {
+            let wire = format!("{}.BTCUSD", topic);
+            assert_eq!(
+                Topic::parse(&wire),
+                Some((*topic, Some("BTCUSD".to_owned())))
+            );
+        }
+        assert_eq!(Topic::orderbook(200).into_string(), "orderBook_200.100ms");
     }
 }
@@ -0,0 +1,200 @@
+use super::core::WebSocketResponse;
+use super::enums::{OrderBookDepth, Topic};
+use super::structs::{
+    instrument, Candle, Execution, Instrument, InstrumentUpdate, Insurance, Limit, Liquidation,
+    Order, Position, Record, StopOrder, Ticker, Wallet,
+};
+use crate::rest::Interval;
+use chrono::{DateTime, Utc};
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+/// Everything a [`WebSocket`](super::WebSocket) yields, in arrival order.
+#[derive(Clone, Debug)]
+pub enum WsEvent {
+    OrderBookSnapshot {
+        depth: OrderBookDepth,
+        symbol: String,
+        cross_seq: Option<u64>,
+        timestamp: Option<DateTime<Utc>>,
+        limits: Vec<Limit>,
+    },
+    OrderBookDelta {
+        depth: OrderBookDepth,
+        symbol: String,
+        cross_seq: Option<u64>,
+        timestamp: Option<DateTime<Utc>>,
+        delete: Vec<Limit>,
+        update: Vec<Limit>,
+        insert: Vec<Limit>,
+    },
+    /// A delta of `symbol` came out of order or without a snapshot. It was dropped,
+    /// as are the next ones, while the book is resubscribed for a fresh snapshot.
+    OrderBookGap {
+        depth: OrderBookDepth,
+        symbol: String,
+        /// The `cross_seq` of the last message applied, `None` without a snapshot.
+        last_seq: Option<u64>,
+        cross_seq: Option<u64>,
+    },
+    Trade(Vec<Record>),
+    Insurance(Vec<Insurance>),
+    InstrumentSnapshot {
+        symbol: String,
+        cross_seq: Option<u64>,
+        timestamp: Option<DateTime<Utc>>,
+        instrument: Instrument,
+    },
+    InstrumentDelta {
+        symbol: String,
+        cross_seq: Option<u64>,
+        timestamp: Option<DateTime<Utc>>,
+        updates: Vec<InstrumentUpdate>,
+    },
+    KLine {
+        symbol: String,
+        interval: Interval,
+        candles: Vec<Candle>,
+    },
+    Liquidation(Vec<Liquidation>),
+    Position(Vec<Position>),
+    Execution(Vec<Execution>),
+    Order(Vec<Order>),
+    StopOrder(Vec<StopOrder>),
+    Wallet(Vec<Wallet>),
+    /// Spot only.
+    Ticker(Vec<Ticker>),
+    /// Reply to an `auth`, `subscribe` or other op.
+    Ack {
+        success: bool,
+        ret_msg: String,
+        op: String,
+        args: Vec<String>,
+    },
+    Pong,
+    /// No update of `topic` (for `symbol`, or any symbol if `None`) arrived within
+    /// the window set with [`WebSocketBuilder::stale_after`](super::WebSocketBuilder::stale_after).
+    Stale {
+        topic: Topic,
+        symbol: Option<String>,
+        silent_for: Duration,
+    },
+    /// Updates of a topic reported [`Stale`](WsEvent::Stale) are flowing again.
+    Recovered {
+        topic: Topic,
+        symbol: Option<String>,
+    },
+    /// The connection dropped and was re-established; subscriptions were replayed
+    /// and fresh snapshots follow.
+    Reconnected,
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct Delta<T> {
+    #[serde(default)]
+    delete: Vec<T>,
+    #[serde(default)]
+    update: Vec<T>,
+    #[serde(default)]
+    insert: Vec<T>,
+}
+
+/// `orderBook_200` snapshots wrap the levels in an `order_book` object.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Snapshot {
+    Limits(Vec<Limit>),
+    OrderBook { order_book: Vec<Limit> },
+}
+
+impl TryFrom<WebSocketResponse> for WsEvent {
+    type Error = serde_json::Error;
+
+    fn try_from(res: WebSocketResponse) -> Result<Self, Self::Error> {
+        let (topic, symbol) = Topic::parse(&res.topic)
+            .ok_or_else(|| serde_json::Error::custom(format!("Unknown topic: {}", res.topic)))?;
+        let symbol = symbol.unwrap_or_default();
+        let WebSocketResponse {
+            msg_type,
+            timestamp,
+            cross_seq,
+            mut data,
+            ..
+        } = res;
+        let is_delta = msg_type.as_deref() == Some("delta");
+        if let Topic::Instrument(_) = topic {
+            instrument::normalize(&mut data);
+        }
+
+        Ok(match topic {
+            Topic::OrderBook(OrderBookDepth::Spot | OrderBookDepth::Merged(_)) | Topic::Ticker => {
+                return Err(serde_json::Error::custom(format!(
+                    "Spot topic {} outside of the spot websocket",
+                    topic.name()
+                )))
+            }
+            Topic::OrderBook(depth) if is_delta => {
+                let delta = serde_json::from_value::<Delta<Limit>>(data)?;
+                WsEvent::OrderBookDelta {
+                    depth,
+                    symbol,
+                    cross_seq,
+                    timestamp,
+                    delete: delta.delete,
+                    update: delta.update,
+                    insert: delta.insert,
+                }
+            }
+            Topic::OrderBook(depth) => WsEvent::OrderBookSnapshot {
+                depth,
+                symbol,
+                cross_seq,
+                timestamp,
+                limits: match serde_json::from_value::<Snapshot>(data)? {
+                    Snapshot::Limits(limits) => limits,
+                    Snapshot::OrderBook { order_book } => order_book,
+                },
+            },
+            Topic::Trade => WsEvent::Trade(from_data(data)?),
+            Topic::Insurance => WsEvent::Insurance(from_data(data)?),
+            Topic::Instrument(_) if is_delta => WsEvent::InstrumentDelta {
+                symbol,
+                cross_seq,
+                timestamp,
+                updates: serde_json::from_value::<Delta<InstrumentUpdate>>(data)?.update,
+            },
+            Topic::Instrument(_) => WsEvent::InstrumentSnapshot {
+                symbol,
+                cross_seq,
+                timestamp,
+                instrument: serde_json::from_value(data)?,
+            },
+            Topic::KLine(interval) => WsEvent::KLine {
+                symbol,
+                interval,
+                candles: from_data(data)?,
+            },
+            Topic::Liquidation => WsEvent::Liquidation(from_data(data)?),
+            Topic::Position => WsEvent::Position(from_data(data)?),
+            Topic::Execution => WsEvent::Execution(from_data(data)?),
+            Topic::Order => WsEvent::Order(from_data(data)?),
+            Topic::StopOrder => WsEvent::StopOrder(from_data(data)?),
+            Topic::Wallet => WsEvent::Wallet(from_data(data)?),
+        })
+    }
+}
+
+/// Private topics batch their updates in an array; accept a bare object too.
+fn from_data<T>(data: Value) -> Result<Vec<T>, serde_json::Error>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    match data {
+        Value::Array(_) => serde_json::from_value(data),
+        _ => serde_json::from_value(data).map(|item| vec![item]),
+    }
+}
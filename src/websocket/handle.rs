@@ -0,0 +1,80 @@
+use async_tungstenite::tungstenite::Error;
+use futures::channel::oneshot;
+
+/// Why the task spawned by [`WebSocket::run_forever`](super::WebSocket::run_forever)
+/// stopped.
+#[derive(Debug)]
+pub enum ExitReason {
+    /// [`WebSocketHandle::close`] was called and the connection was closed.
+    Shutdown,
+    /// Reconnecting was given up after this error.
+    Disconnected(Error),
+    /// The task ended without reporting, e.g. because it panicked.
+    Aborted,
+}
+
+/// Controls the task spawned by [`WebSocket::run_forever`](super::WebSocket::run_forever).
+///
+/// Dropping the handle leaves the task running.
+#[must_use = "dropping the handle detaches the task, which can then no longer be closed"]
+pub struct WebSocketHandle {
+    shutdown: Option<oneshot::Sender<()>>,
+    exit: oneshot::Receiver<ExitReason>,
+}
+
+impl WebSocketHandle {
+    pub(crate) fn new(shutdown: oneshot::Sender<()>, exit: oneshot::Receiver<ExitReason>) -> Self {
+        WebSocketHandle {
+            shutdown: Some(shutdown),
+            exit,
+        }
+    }
+
+    /// Asks the task to send a Close frame, flush and stop. Returns immediately;
+    /// use [`join`](Self::join) to wait for it.
+    pub fn close(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+
+    /// Waits for the task to stop.
+    pub async fn join(self) -> ExitReason {
+        self.exit.await.unwrap_or(ExitReason::Aborted)
+    }
+
+    /// [`close`](Self::close) then [`join`](Self::join).
+    pub async fn shutdown(mut self) -> ExitReason {
+        self.close();
+        self.join().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExitReason, WebSocketHandle};
+    use futures::{channel::oneshot, executor::block_on};
+
+    #[test]
+    fn test_shutdown() {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let handle = WebSocketHandle::new(shutdown_tx, exit_rx);
+
+        std::thread::spawn(move || {
+            block_on(shutdown_rx).unwrap();
+            exit_tx.send(ExitReason::Shutdown).unwrap();
+        });
+
+        assert!(matches!(block_on(handle.shutdown()), ExitReason::Shutdown));
+    }
+
+    #[test]
+    fn test_aborted() {
+        let (shutdown_tx, _) = oneshot::channel();
+        let (_, exit_rx) = oneshot::channel();
+        let handle = WebSocketHandle::new(shutdown_tx, exit_rx);
+
+        assert!(matches!(block_on(handle.join()), ExitReason::Aborted));
+    }
+}
@@ -0,0 +1,142 @@
+use super::enums::Topic;
+use super::event::WsEvent;
+use std::time::{Duration, Instant};
+
+/// Timer-driven pings used by [`WebSocket::next_event`](super::WebSocket::next_event).
+///
+/// A ping is sent every `ping_interval` whether or not anything arrives; if its pong
+/// is not back within `pong_timeout` the connection is considered dead and is
+/// re-established.
+#[derive(Clone, Debug)]
+pub struct HeartbeatPolicy {
+    pub ping_interval: Duration,
+    pub pong_timeout: Duration,
+}
+
+impl Default for HeartbeatPolicy {
+    fn default() -> Self {
+        HeartbeatPolicy {
+            ping_interval: Duration::from_secs(30),
+            pong_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Raises [`WsEvent::Stale`] when `topic` has been silent for `after`.
+#[derive(Clone, Debug)]
+pub(crate) struct StaleWatch {
+    topic: Topic,
+    symbol: Option<String>,
+    after: Duration,
+    last_update: Instant,
+    stale: bool,
+}
+
+impl StaleWatch {
+    /// A `*` symbol watches every symbol of the topic together.
+    pub(crate) fn new(topic: Topic, symbol: &str, after: Duration) -> Self {
+        StaleWatch {
+            topic,
+            symbol: Some(symbol.to_owned()).filter(|s| s != "*"),
+            after,
+            last_update: Instant::now(),
+            stale: false,
+        }
+    }
+
+    fn matches(&self, topic: Topic, symbol: Option<&str>) -> bool {
+        self.topic == topic && (self.symbol.is_none() || self.symbol.as_deref() == symbol)
+    }
+
+    fn event(&self) -> WsEvent {
+        if self.stale {
+            WsEvent::Stale {
+                topic: self.topic,
+                symbol: self.symbol.clone(),
+                silent_for: self.last_update.elapsed(),
+            }
+        } else {
+            WsEvent::Recovered {
+                topic: self.topic,
+                symbol: self.symbol.clone(),
+            }
+        }
+    }
+}
+
+/// Records an update of `topic` and returns the events of watches it revives.
+pub(crate) fn touch(
+    watches: &mut [StaleWatch],
+    topic: Topic,
+    symbol: Option<&str>,
+) -> Vec<WsEvent> {
+    watches
+        .iter_mut()
+        .filter(|watch| watch.matches(topic, symbol))
+        .filter_map(|watch| {
+            watch.last_update = Instant::now();
+            if watch.stale {
+                watch.stale = false;
+                Some(watch.event())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Marks the watches that went silent and returns their events.
+pub(crate) fn expire(watches: &mut [StaleWatch], now: Instant) -> Vec<WsEvent> {
+    watches
+        .iter_mut()
+        .filter(|watch| !watch.stale && now >= watch.last_update + watch.after)
+        .map(|watch| {
+            watch.stale = true;
+            watch.event()
+        })
+        .collect()
+}
+
+/// When the next watch goes silent.
+pub(crate) fn next_deadline(watches: &[StaleWatch]) -> Option<Instant> {
+    watches
+        .iter()
+        .filter(|watch| !watch.stale)
+        .map(|watch| watch.last_update + watch.after)
+        .min()
+}
+
+/// Restarts every watch, e.g. after a reconnect.
+pub(crate) fn restart(watches: &mut [StaleWatch]) {
+    let now = Instant::now();
+    watches.iter_mut().for_each(|watch| {
+        watch.last_update = now;
+        watch.stale = false;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expire, touch, StaleWatch};
+    use crate::websocket::{Topic, WsEvent};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_stale_watch() {
+        let mut watches = vec![StaleWatch::new(
+            Topic::orderbook(25),
+            "BTCUSD",
+            Duration::from_secs(5),
+        )];
+        let now = Instant::now();
+
+        assert!(expire(&mut watches, now).is_empty());
+        let stale = expire(&mut watches, now + Duration::from_secs(6));
+        assert!(matches!(stale[..], [WsEvent::Stale { .. }]));
+        assert!(expire(&mut watches, now + Duration::from_secs(7)).is_empty());
+
+        assert!(touch(&mut watches, Topic::orderbook(25), Some("ETHUSD")).is_empty());
+        let recovered = touch(&mut watches, Topic::orderbook(25), Some("BTCUSD"));
+        assert!(matches!(recovered[..], [WsEvent::Recovered { .. }]));
+    }
+}
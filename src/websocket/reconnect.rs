@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+/// Exponential backoff used by [`WebSocket::reconnect`](super::WebSocket::reconnect).
+///
+/// The first attempt is made immediately; each retry waits twice as long as the
+/// previous one, capped at `max_delay`. `max_retries: None` retries forever.
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub max_retries: Option<usize>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_retries: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub(crate) fn delays(&self) -> impl Iterator<Item = Duration> {
+        let max_delay = self.max_delay;
+        let delays = std::iter::successors(Some(self.initial_delay), move |delay| {
+            Some((*delay * 2).min(max_delay))
+        });
+
+        delays.take(self.max_retries.unwrap_or(usize::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReconnectPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_delays() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            max_retries: Some(5),
+        };
+
+        assert_eq!(
+            policy.delays().map(|d| d.as_secs()).collect::<Vec<_>>(),
+            vec![1, 2, 4, 5, 5]
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use super::enums::Channel;
+use super::subscription::SubscriptionRegistry;
+use crate::trace::{self, Direction};
+use async_tungstenite::tungstenite::protocol::Message;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Timelike, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::warn;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+
+/// Writes every frame of a [`WebSocket`](super::WebSocket) to gzipped JSON lines,
+/// see [`WebSocketBuilder::recorder`](super::WebSocketBuilder::recorder).
+///
+/// Each file opens with a `header` line listing the subscriptions in effect,
+/// followed by one `frame` line per frame with the local time in nanoseconds and
+/// the id of the connection. Text frames are kept as is, except for credentials
+/// in `auth` frames which are redacted; the payload of other frames is base64.
+///
+/// Files are written on a thread of their own, which buffers up to 4096 frames
+/// when it falls behind, e.g. on a slow disk. Frames beyond that are dropped
+/// rather than holding up the socket; their count is logged and written to the
+/// file as a `dropped` line.
+///
+/// ```no_run
+/// # use bybit_rs::websocket::Recorder;
+/// let recorder = Recorder::new("data/bybit")
+///     .prefix("btcusd")
+///     .max_bytes(64 * 1024 * 1024);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: Option<u64>,
+    hourly: bool,
+}
+
+/// The sending side of a running [`Recorder`].
+#[derive(Debug)]
+pub(crate) struct RecorderHandle {
+    sender: SyncSender<Command>,
+    /// Frames dropped since the last one sent.
+    dropped: AtomicU64,
+}
+
+#[derive(Debug)]
+enum Command {
+    Frame(Frame),
+    /// Finishes the current file; the next frame opens a new one.
+    Finish,
+}
+
+#[derive(Debug)]
+pub(crate) struct Frame {
+    timestamp: DateTime<Utc>,
+    connection: u64,
+    direction: Direction,
+    kind: &'static str,
+    payload: String,
+    /// Frames dropped right before this one.
+    dropped: u64,
+}
+
+struct Writer {
+    recorder: Recorder,
+    channel: Channel,
+    registry: SubscriptionRegistry,
+    files: u64,
+    file: Option<RecordFile>,
+}
+
+struct RecordFile {
+    encoder: GzEncoder<Counted<BufWriter<File>>>,
+    opened_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Line<'a> {
+    Header {
+        created_ns: i64,
+        connection: u64,
+        channel: &'a str,
+        subscriptions: Vec<String>,
+    },
+    Frame {
+        ts_ns: i64,
+        connection: u64,
+        direction: &'a str,
+        kind: &'a str,
+        payload: &'a str,
+    },
+    /// Frames the writer fell too far behind to record.
+    Dropped { ts_ns: i64, frames: u64 },
+}
+
+const FRAME_BUFFER: usize = 4096;
+
+impl Recorder {
+    /// Rotates every hour and every 256 MiB by default.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Recorder {
+            dir: dir.into(),
+            prefix: "bybit".to_owned(),
+            max_bytes: Some(256 * 1024 * 1024),
+            hourly: true,
+        }
+    }
+
+    /// Files are named `<prefix>-<UTC time>-<n>.jsonl.gz`, `bybit` by default, with
+    /// `n` counting from `0000`.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Rotates once a file holds `bytes` compressed, checked between frames.
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    pub fn unlimited_size(mut self) -> Self {
+        self.max_bytes = None;
+        self
+    }
+
+    /// Whether to rotate when the UTC hour changes.
+    pub fn hourly(mut self, hourly: bool) -> Self {
+        self.hourly = hourly;
+        self
+    }
+
+    /// Starts the thread writing the frames of a socket on `channel`, which
+    /// finishes the file and stops once the handle is dropped.
+    pub(crate) fn spawn(
+        &self,
+        channel: Channel,
+        registry: SubscriptionRegistry,
+    ) -> io::Result<RecorderHandle> {
+        let (sender, receiver) = mpsc::sync_channel(FRAME_BUFFER);
+        let writer = Writer::new(self.clone(), channel, registry);
+        thread::Builder::new()
+            .name("bybit-recorder".to_owned())
+            .spawn(move || writer.run(receiver))?;
+
+        Ok(RecorderHandle {
+            sender,
+            dropped: AtomicU64::new(0),
+        })
+    }
+}
+
+impl RecorderHandle {
+    pub(crate) fn record(&self, mut frame: Frame) {
+        frame.dropped = self.dropped.load(Ordering::Relaxed);
+        match self.sender.try_send(Command::Frame(frame)) {
+            Ok(()) => self.dropped.store(0, Ordering::Relaxed),
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+
+    pub(crate) fn finish(&self) {
+        let _ = self.sender.try_send(Command::Finish);
+    }
+}
+
+impl Drop for RecorderHandle {
+    fn drop(&mut self) {
+        let dropped = *self.dropped.get_mut();
+        if dropped > 0 {
+            warn!(
+                "Recorder fell behind and dropped the last {} frames",
+                dropped
+            );
+        }
+    }
+}
+
+impl Frame {
+    pub(crate) fn new(
+        timestamp: DateTime<Utc>,
+        connection: u64,
+        direction: Direction,
+        msg: &Message,
+    ) -> Self {
+        let (kind, payload) = match msg {
+            Message::Text(text) => ("text", trace::redact_frame(text)),
+            Message::Binary(bytes) => ("binary", STANDARD.encode(bytes)),
+            Message::Ping(bytes) => ("ping", STANDARD.encode(bytes)),
+            Message::Pong(bytes) => ("pong", STANDARD.encode(bytes)),
+            Message::Close(frame) => {
+                // As on the wire: the code, big-endian, then the reason.
+                let bytes = frame.as_ref().map_or_else(Vec::new, |frame| {
+                    let mut bytes = u16::from(frame.code).to_be_bytes().to_vec();
+                    bytes.extend(frame.reason.as_bytes());
+                    bytes
+                });
+                ("close", STANDARD.encode(bytes))
+            }
+        };
+
+        Frame {
+            timestamp,
+            connection,
+            direction,
+            kind,
+            payload,
+            dropped: 0,
+        }
+    }
+}
+
+impl Writer {
+    fn new(recorder: Recorder, channel: Channel, registry: SubscriptionRegistry) -> Self {
+        Writer {
+            recorder,
+            channel,
+            registry,
+            files: 0,
+            file: None,
+        }
+    }
+
+    /// Writes frames until every sender is dropped.
+    fn run(mut self, receiver: Receiver<Command>) {
+        for command in receiver {
+            let result = match command {
+                Command::Frame(frame) => self.write(&frame),
+                Command::Finish => self.close(),
+            };
+            if let Err(err) = result {
+                warn!("Failed to record frames: {}", err);
+                let _ = self.close();
+            }
+        }
+
+        if let Err(err) = self.close() {
+            warn!("Failed to finish recording: {}", err);
+        }
+    }
+
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        if self.is_due(frame.timestamp) {
+            self.close()?;
+        }
+        if self.file.is_none() {
+            self.open(frame.timestamp, frame.connection)?;
+        }
+        if frame.dropped > 0 {
+            warn!("Recorder fell behind and dropped {} frames", frame.dropped);
+            let line = Line::Dropped {
+                ts_ns: nanos(frame.timestamp),
+                frames: frame.dropped,
+            };
+            self.write_line(&line)?;
+        }
+
+        let line = Line::Frame {
+            ts_ns: nanos(frame.timestamp),
+            connection: frame.connection,
+            direction: match frame.direction {
+                Direction::Inbound => "in",
+                Direction::Outbound => "out",
+            },
+            kind: frame.kind,
+            payload: &frame.payload,
+        };
+        self.write_line(&line)
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(file) => file.encoder.finish()?.inner.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return false,
+        };
+        let full = self
+            .recorder
+            .max_bytes
+            .is_some_and(|max_bytes| file.encoder.get_ref().bytes >= max_bytes);
+        let hour = |t: DateTime<Utc>| (t.date_naive(), t.hour());
+        full || (self.recorder.hourly && hour(now) != hour(file.opened_at))
+    }
+
+    fn open(&mut self, now: DateTime<Utc>, connection: u64) -> io::Result<()> {
+        fs::create_dir_all(&self.recorder.dir)?;
+        let path = self.recorder.dir.join(format!(
+            "{}-{}-{:04}.jsonl.gz",
+            self.recorder.prefix,
+            now.format("%Y%m%dT%H%M%S"),
+            self.files
+        ));
+        let file = File::create(path)?;
+        self.files += 1;
+        self.file = Some(RecordFile {
+            encoder: GzEncoder::new(
+                Counted {
+                    inner: BufWriter::new(file),
+                    bytes: 0,
+                },
+                Compression::default(),
+            ),
+            opened_at: now,
+        });
+
+        let header = Line::Header {
+            created_ns: nanos(now),
+            connection,
+            channel: self.channel.path(),
+            subscriptions: self.registry.args(),
+        };
+        self.write_line(&header)
+    }
+
+    fn write_line(&mut self, line: &Line) -> io::Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        serde_json::to_writer(&mut file.encoder, line)?;
+        file.encoder.write_all(b"\n")
+    }
+}
+
+fn nanos(timestamp: DateTime<Utc>) -> i64 {
+    timestamp.timestamp_nanos_opt().unwrap_or_default()
+}
+
+/// Counts the compressed bytes written to a file.
+struct Counted<W> {
+    inner: W,
+    bytes: u64,
+}
+
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Frame, Recorder, RecorderHandle, Writer};
+    use crate::trace::Direction;
+    use crate::websocket::enums::Channel;
+    use crate::websocket::subscription::SubscriptionRegistry;
+    use async_tungstenite::tungstenite::protocol::Message;
+    use chrono::Utc;
+    use flate2::read::GzDecoder;
+    use serde_json::Value;
+    use std::fs::{self, File};
+    use std::io::{BufRead, BufReader};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_recorder() {
+        let dir = std::env::temp_dir().join(format!("bybit-recorder-{}", std::process::id()));
+        let registry = SubscriptionRegistry::default();
+        registry.insert(&["trade.BTCUSD".to_owned()]);
+
+        let recorder = Recorder::new(&dir).max_bytes(1);
+        let writer = Writer::new(recorder, Channel::Inverse, registry);
+        let (sender, receiver) = mpsc::sync_channel(3);
+        let now = Utc::now();
+        let frames = [
+            Message::text(r#"{"op":"ping"}"#),
+            Message::text(r#"{"ret_msg":"pong"}"#),
+            Message::Ping(vec![1, 2, 3]),
+        ];
+        for (i, msg) in frames.iter().enumerate() {
+            let direction = match i {
+                0 => Direction::Outbound,
+                _ => Direction::Inbound,
+            };
+            let frame = Frame::new(now, 7, direction, msg);
+            sender.send(Command::Frame(frame)).unwrap();
+        }
+        drop(sender);
+        writer.run(receiver);
+
+        let paths = files(&dir);
+        assert_eq!(paths.len(), 3);
+        assert!(paths[2].to_str().unwrap().ends_with("-0002.jsonl.gz"));
+
+        let lines = read(&paths[1]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "header");
+        assert_eq!(lines[0]["channel"], "realtime");
+        assert_eq!(lines[0]["subscriptions"][0], "trade.BTCUSD");
+        assert_eq!(lines[1]["type"], "frame");
+        assert_eq!(lines[1]["connection"], 7);
+        assert_eq!(lines[1]["direction"], "in");
+        assert_eq!(lines[1]["kind"], "text");
+        assert_eq!(lines[1]["payload"], r#"{"ret_msg":"pong"}"#);
+        assert_eq!(lines[1]["ts_ns"], now.timestamp_nanos_opt().unwrap());
+
+        let ping = &read(&paths[2])[1];
+        assert_eq!(ping["kind"], "ping");
+        assert_eq!(ping["payload"], "AQID");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dropped_frames() {
+        let dir = std::env::temp_dir().join(format!("bybit-dropped-{}", std::process::id()));
+        let mut writer = Writer::new(
+            Recorder::new(&dir),
+            Channel::Inverse,
+            SubscriptionRegistry::default(),
+        );
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let handle = RecorderHandle {
+            sender,
+            dropped: AtomicU64::new(0),
+        };
+        let frame =
+            |text: &str| Frame::new(Utc::now(), 0, Direction::Inbound, &Message::text(text));
+
+        handle.record(frame("first"));
+        handle.record(frame("dropped"));
+        assert_eq!(handle.dropped.load(Ordering::Relaxed), 1);
+
+        match receiver.recv().unwrap() {
+            Command::Frame(first) => writer.write(&first).unwrap(),
+            Command::Finish => unreachable!(),
+        }
+        handle.record(frame("last"));
+        assert_eq!(handle.dropped.load(Ordering::Relaxed), 0);
+        drop(handle);
+        writer.run(receiver);
+
+        let lines = read(&files(&dir)[0]);
+        let types: Vec<_> = lines
+            .iter()
+            .map(|line| line["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["header", "frame", "dropped", "frame"]);
+        assert_eq!(lines[2]["frames"], 1);
+        assert_eq!(lines[3]["payload"], "last");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn files(dir: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn read(path: &Path) -> Vec<Value> {
+        BufReader::new(GzDecoder::new(File::open(path).unwrap()))
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect()
+    }
+}
@@ -0,0 +1,140 @@
+use super::enums::OrderBookDepth;
+use super::event::WsEvent;
+use std::collections::HashMap;
+
+/// What to do with an orderbook event, see [`Sequencer::check`].
+#[derive(Debug)]
+pub(crate) enum Verdict {
+    Apply,
+    /// A delta of a book awaiting its new snapshot.
+    Drop,
+    /// The delta doesn't follow the book, whose last `cross_seq` was `last_seq`;
+    /// yield an [`OrderBookGap`](WsEvent::OrderBookGap) instead and resync.
+    Gap {
+        last_seq: Option<u64>,
+    },
+}
+
+#[derive(Debug)]
+enum Book {
+    /// The `cross_seq` of the last message applied.
+    Synced(Option<u64>),
+    Resyncing,
+}
+
+/// Tracks the `cross_seq` of every orderbook per depth and symbol; each depth is a
+/// book of its own with a sequence of its own.
+///
+/// `cross_seq` grows with every match of the symbol but deltas are batched, so it
+/// skips values, and repeats them when no match happened in between. A gap shows
+/// as a delta that arrives before any snapshot, and reordering as one whose
+/// `cross_seq` is below the last one applied.
+#[derive(Debug, Default)]
+pub(crate) struct Sequencer {
+    books: HashMap<(OrderBookDepth, String), Book>,
+}
+
+impl Sequencer {
+    pub(crate) fn check(&mut self, event: &WsEvent) -> Verdict {
+        let (key, cross_seq) = match event {
+            WsEvent::OrderBookSnapshot {
+                depth,
+                symbol,
+                cross_seq,
+                ..
+            } => {
+                self.books
+                    .insert((*depth, symbol.clone()), Book::Synced(*cross_seq));
+                return Verdict::Apply;
+            }
+            WsEvent::OrderBookDelta {
+                depth,
+                symbol,
+                cross_seq,
+                ..
+            } => ((*depth, symbol.clone()), *cross_seq),
+            _ => return Verdict::Apply,
+        };
+
+        let last_seq = match self.books.get_mut(&key) {
+            Some(Book::Resyncing) => return Verdict::Drop,
+            Some(Book::Synced(last)) => match (*last, cross_seq) {
+                (Some(last), Some(seq)) if seq < last => Some(last),
+                _ => {
+                    *last = cross_seq.or(*last);
+                    return Verdict::Apply;
+                }
+            },
+            None => None,
+        };
+
+        self.books.insert(key, Book::Resyncing);
+        Verdict::Gap { last_seq }
+    }
+
+    /// Every book awaits a new snapshot after a reconnect.
+    pub(crate) fn reset(&mut self) {
+        self.books.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sequencer, Verdict};
+    use crate::websocket::{Frequency, OrderBookDepth, WsEvent};
+
+    fn snapshot(cross_seq: u64) -> WsEvent {
+        WsEvent::OrderBookSnapshot {
+            depth: OrderBookDepth::L25,
+            symbol: "BTCUSD".to_owned(),
+            cross_seq: Some(cross_seq),
+            timestamp: None,
+            limits: Vec::new(),
+        }
+    }
+
+    fn delta(cross_seq: u64) -> WsEvent {
+        delta_of(OrderBookDepth::L25, cross_seq)
+    }
+
+    fn delta_of(depth: OrderBookDepth, cross_seq: u64) -> WsEvent {
+        WsEvent::OrderBookDelta {
+            depth,
+            symbol: "BTCUSD".to_owned(),
+            cross_seq: Some(cross_seq),
+            timestamp: None,
+            delete: Vec::new(),
+            update: Vec::new(),
+            insert: Vec::new(),
+        }
+    }
+
+    fn is_gap(verdict: Verdict, expected: Option<u64>) -> bool {
+        matches!(verdict, Verdict::Gap { last_seq } if last_seq == expected)
+    }
+
+    #[test]
+    fn test_sequence() {
+        let mut sequencer = Sequencer::default();
+        assert!(is_gap(sequencer.check(&delta(10)), None));
+        assert!(matches!(sequencer.check(&delta(11)), Verdict::Drop));
+
+        assert!(matches!(sequencer.check(&snapshot(20)), Verdict::Apply));
+        assert!(matches!(sequencer.check(&delta(25)), Verdict::Apply));
+        // No match between two batches.
+        assert!(matches!(sequencer.check(&delta(25)), Verdict::Apply));
+        assert!(is_gap(sequencer.check(&delta(24)), Some(25)));
+        assert!(matches!(sequencer.check(&delta(30)), Verdict::Drop));
+
+        assert!(matches!(sequencer.check(&snapshot(40)), Verdict::Apply));
+        assert!(matches!(sequencer.check(&delta(41)), Verdict::Apply));
+
+        // The 200 levels book has a sequence of its own.
+        let l200 = OrderBookDepth::L200(Frequency::default());
+        assert!(is_gap(sequencer.check(&delta_of(l200, 35)), None));
+        assert!(matches!(sequencer.check(&delta(42)), Verdict::Apply));
+
+        sequencer.reset();
+        assert!(is_gap(sequencer.check(&delta(43)), None));
+    }
+}
@@ -0,0 +1,325 @@
+//! The protocol of the spot websockets, translated to and from the `op` requests
+//! and [`WsEvent`]s of the derivatives ones.
+//!
+//! Spot subscribes with a frame per topic and symbol, pings with `{"ping": ..}` and
+//! pushes private updates as arrays keyed by event type.
+
+use super::enums::Topic;
+use super::event::WsEvent;
+use super::structs::spot::{
+    SpotAccount, SpotDepth, SpotExecutionReport, SpotKline, SpotTicket, SpotTrade,
+};
+use super::structs::WsArgs;
+use async_tungstenite::tungstenite::protocol::Message;
+use chrono::Utc;
+use serde::de::Error as _;
+use serde_json::{json, Map, Value};
+
+/// A parsed spot frame and the topic its updates count towards for staleness.
+pub(crate) struct Frame {
+    pub topic: Option<(Topic, Option<String>)>,
+    pub events: Vec<WsEvent>,
+}
+
+/// The spot frames of `args`; `auth` is the same as for derivatives.
+pub(crate) fn messages(args: WsArgs) -> Vec<Message> {
+    let event = match args.op.as_str() {
+        "ping" => return vec![text(json!({ "ping": Utc::now().timestamp_millis() }))],
+        "subscribe" => "sub",
+        "unsubscribe" => "cancel",
+        _ => return vec![args.into_msg()],
+    };
+
+    args.args
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|arg| {
+            let (_, symbol) = Topic::parse(arg)?;
+            let name = arg.split('.').next()?;
+            let mut params = Map::new();
+            params.insert("binary".to_owned(), Value::Bool(false));
+            if let Some(key) = param_key(name) {
+                let param = arg.split('.').nth(1).unwrap_or_default();
+                let value = param.parse::<u32>().map_or(json!(param), |n| json!(n));
+                params.insert(key.to_owned(), value);
+            }
+            Some(text(json!({
+                "topic": name,
+                "event": event,
+                "symbol": symbol,
+                "params": params,
+            })))
+        })
+        .collect()
+}
+
+pub(crate) fn parse(text: &str) -> serde_json::Result<Frame> {
+    match serde_json::from_str::<Value>(text)? {
+        Value::Array(updates) => parse_private(updates),
+        Value::Object(frame) if frame.contains_key("pong") => Ok(Frame {
+            topic: None,
+            events: vec![WsEvent::Pong],
+        }),
+        Value::Object(frame) if frame.contains_key("data") => parse_public(frame),
+        Value::Object(frame) => Ok(Frame {
+            topic: None,
+            events: vec![ack(&frame)],
+        }),
+        frame => Err(serde_json::Error::custom(format!(
+            "Unexpected spot frame: {}",
+            frame
+        ))),
+    }
+}
+
+fn parse_public(mut frame: Map<String, Value>) -> serde_json::Result<Frame> {
+    let (topic, symbol) = arg(&frame)
+        .and_then(|arg| Topic::parse(&arg))
+        .ok_or_else(|| {
+            serde_json::Error::custom(format!("Unknown spot topic: {}", frame["topic"]))
+        })?;
+    let symbol = symbol.unwrap_or_default();
+    let data = frame.remove("data").unwrap_or_default();
+
+    let events = match topic {
+        Topic::Trade => {
+            let trades: Vec<SpotTrade> = from_data(data)?;
+            vec![WsEvent::Trade(
+                trades
+                    .into_iter()
+                    .map(|trade| trade.into_record(&symbol))
+                    .collect(),
+            )]
+        }
+        Topic::OrderBook(depth) => from_data::<SpotDepth>(data)?
+            .into_iter()
+            .map(|book| {
+                Ok(WsEvent::OrderBookSnapshot {
+                    depth,
+                    limits: book.limits().map_err(serde_json::Error::custom)?,
+                    symbol: book.symbol,
+                    cross_seq: None,
+                    timestamp: Some(book.timestamp),
+                })
+            })
+            .collect::<serde_json::Result<_>>()?,
+        Topic::KLine(interval) => {
+            let klines: Vec<SpotKline> = from_data(data)?;
+            vec![WsEvent::KLine {
+                symbol: symbol.clone(),
+                interval,
+                candles: klines
+                    .into_iter()
+                    .map(|kline| kline.into_candle(interval))
+                    .collect(),
+            }]
+        }
+        Topic::Ticker => vec![WsEvent::Ticker(from_data(data)?)],
+        topic => {
+            return Err(serde_json::Error::custom(format!(
+                "Not a spot topic: {}",
+                topic.name()
+            )))
+        }
+    };
+
+    Ok(Frame {
+        topic: Some((topic, Some(symbol))),
+        events,
+    })
+}
+
+fn parse_private(updates: Vec<Value>) -> serde_json::Result<Frame> {
+    let mut topic = None;
+    let mut events = Vec::new();
+
+    for update in updates {
+        let (update_topic, event) = match update["e"].as_str().unwrap_or_default() {
+            "outboundAccountInfo" => {
+                let account: SpotAccount = serde_json::from_value(update)?;
+                (
+                    Topic::Wallet,
+                    WsEvent::Wallet(account.into_wallets().collect()),
+                )
+            }
+            "executionReport" => {
+                let report: SpotExecutionReport = serde_json::from_value(update)?;
+                (Topic::Order, WsEvent::Order(vec![report.into()]))
+            }
+            "ticketInfo" => {
+                let ticket: SpotTicket = serde_json::from_value(update)?;
+                (Topic::Execution, WsEvent::Execution(vec![ticket.into()]))
+            }
+            e => {
+                return Err(serde_json::Error::custom(format!(
+                    "Unknown spot event: {}",
+                    e
+                )))
+            }
+        };
+        topic.get_or_insert((update_topic, None));
+        events.push(event);
+    }
+
+    Ok(Frame { topic, events })
+}
+
+/// Spot acks echo the request, from which its `subscribe` argument is rebuilt.
+fn ack(frame: &Map<String, Value>) -> WsEvent {
+    let success = match &frame.get("code") {
+        Some(Value::String(code)) => code == "0",
+        Some(Value::Number(code)) => code.as_i64() == Some(0),
+        _ => false,
+    };
+    let op = match frame.get("event").and_then(Value::as_str) {
+        Some("sub") => "subscribe",
+        Some("cancel") => "unsubscribe",
+        Some(event) => event,
+        None => "",
+    };
+    let args = arg(frame).into_iter().collect();
+    let ret_msg = ["msg", "desc"]
+        .iter()
+        .find_map(|key| frame.get(*key).and_then(Value::as_str))
+        .unwrap_or_default();
+
+    WsEvent::Ack {
+        success,
+        ret_msg: ret_msg.to_owned(),
+        op: op.to_owned(),
+        args,
+    }
+}
+
+/// The `subscribe` argument a frame echoes, e.g. `kline.1m.BTCUSDT`.
+fn arg(frame: &Map<String, Value>) -> Option<String> {
+    let name = frame.get("topic")?.as_str()?;
+    let prefix = match param_key(name).and_then(|key| param(frame, key)) {
+        Some(param) => format!("{}.{}", name, param),
+        None => name.to_owned(),
+    };
+    Some(format!("{}.{}", prefix, symbol(frame)))
+}
+
+/// The key of the parameter of the topic `name` in the spot `params`.
+fn param_key(name: &str) -> Option<&'static str> {
+    match name {
+        "kline" => Some("klineType"),
+        "mergedDepth" => Some("dumpScale"),
+        _ => None,
+    }
+}
+
+fn param(frame: &Map<String, Value>, key: &str) -> Option<String> {
+    match &frame.get("params")?[key] {
+        Value::String(param) => Some(param.clone()),
+        Value::Number(param) => Some(param.to_string()),
+        _ => None,
+    }
+}
+
+/// Top level in v1 frames, among the parameters in v2 ones.
+fn symbol(frame: &Map<String, Value>) -> String {
+    frame
+        .get("symbol")
+        .or_else(|| frame.get("params").and_then(|params| params.get("symbol")))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn from_data<T>(data: Value) -> serde_json::Result<Vec<T>>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    match data {
+        Value::Array(_) => serde_json::from_value(data),
+        _ => serde_json::from_value(data).map(|item| vec![item]),
+    }
+}
+
+fn text(frame: Value) -> Message {
+    Message::text(frame.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{messages, parse};
+    use crate::rest::{Interval, OrderStatus};
+    use crate::websocket::store::Store;
+    use crate::websocket::structs::WsArgs;
+    use crate::websocket::{OrderBookDepth, WsEvent};
+
+    #[test]
+    fn test_subscribe_ack() {
+        let subscribe = WsArgs {
+            op: "subscribe".to_owned(),
+            args: Some(vec![
+                "kline.1m.BTCUSDT".to_owned(),
+                "trade.ETHUSDT".to_owned(),
+            ]),
+        };
+        let frames = messages(subscribe);
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].to_text().unwrap().contains(r#""klineType":"1m""#));
+
+        let ack = r#"{"symbol":"BTCUSDT","topic":"kline","event":"sub","params":{"binary":"false","klineType":"1m"},"code":"0","msg":"Success"}"#;
+        match &parse(ack).unwrap().events[..] {
+            [WsEvent::Ack {
+                success, op, args, ..
+            }] => {
+                assert!(success);
+                assert_eq!(op, "subscribe");
+                assert_eq!(args, &vec!["kline.1m.BTCUSDT".to_owned()]);
+            }
+            events => panic!("Unexpected events: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        let frames = [
+            r#"{"symbol":"BTCUSDT","symbolName":"BTCUSDT","topic":"trade","params":{"binary":"false"},"data":[{"v":"564265886622695424","t":1582001735462,"p":"9787.5","q":"0.195009","m":true}]}"#,
+            r#"{"symbol":"BTCUSDT","symbolName":"BTCUSDT","topic":"depth","params":{"binary":"false"},"data":[{"s":"BTCUSDT","t":1582001376853,"v":"13850022_2","b":[["9780.79","0.01"],["9780.5","0.1"]],"a":[["9781.21","0.042842"]]}]}"#,
+            r#"{"symbol":"BTCUSDT","symbolName":"BTCUSDT","topic":"kline","params":{"binary":"false","klineType":"1m"},"data":[{"t":1582001100000,"s":"BTCUSDT","sn":"BTCUSDT","c":"9779.54","h":"9780.0","l":"9779.54","o":"9780.0","v":"0.011"}]}"#,
+            r#"{"symbol":"BTCUSDT","symbolName":"BTCUSDT","topic":"realtimes","params":{"binary":"false"},"data":[{"t":1582001100000,"s":"BTCUSDT","o":"9780.0","h":"9800.0","l":"9700.0","c":"9779.54","v":"1000.5","qv":"9779540.5","m":"-0.0001"}]}"#,
+            r#"[{"e":"outboundAccountInfo","E":"1629969654753","T":true,"W":true,"D":true,"B":[{"a":"USDT","f":"900.5","l":"100"}]}]"#,
+            r#"[{"e":"executionReport","E":"1499405658658","s":"BTCUSDT","c":"1000087761","S":"BUY","o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","X":"PARTIALLY_FILLED","i":"4293153","M":"0","l":"0.25","z":"0.25","L":"0.10264410","n":"0","N":"BTC","u":true,"w":true,"m":false,"O":"1499405658657","Z":"0.0256610","A":"0","C":false,"v":"0"}]"#,
+            r#"[{"e":"ticketInfo","E":"1621912542359","s":"BTCUSDT","q":"0.25","t":"1621912542314","p":"0.10264410","T":"899062000267837441","o":"4293153","c":"1000087761","O":"899062000118679808","a":"10043","A":"10024","m":true,"S":"BUY"}]"#,
+        ];
+
+        let mut store = Store::default();
+        for frame in frames.iter() {
+            parse(frame)
+                .unwrap()
+                .events
+                .iter()
+                .for_each(|event| store.apply(event));
+        }
+
+        assert_eq!(store.trading_records()[0].size, 0.195009);
+        assert_eq!(
+            store
+                .orderbook(OrderBookDepth::Spot, "BTCUSDT")
+                .unwrap()
+                .limits
+                .len(),
+            3
+        );
+        assert_eq!(
+            store
+                .candles("BTCUSDT", Interval::OneMin)
+                .unwrap()
+                .forming()
+                .unwrap()
+                .close,
+            9779.54
+        );
+        assert_eq!(store.ticker("BTCUSDT").unwrap().close, 9779.54);
+        assert_eq!(store.wallet("USDT").unwrap().wallet_balance, 1000.5);
+        let order = store.order_by_link_id("1000087761").unwrap();
+        assert_eq!(order.order_status, OrderStatus::PartiallyFilled);
+        assert_eq!(order.leaves_qty, 0.75);
+        assert!(store.fills("BTCUSDT").unwrap()[0].is_maker);
+    }
+}
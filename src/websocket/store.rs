@@ -1,158 +1,470 @@
-use super::core::WebSocketResponse;
-use super::structs::{Instrument, Limit, OrderBook, Position, Record};
-use chrono::Utc;
+use super::enums::{OrderBookDepth, Topic};
+use super::event::WsEvent;
+use super::structs::{
+    CandleSeries, Execution, Instrument, Insurance, Limit, Liquidation, Order, OrderBook, Position,
+    Record, StopOrder, Ticker, Wallet,
+};
+use crate::rest::{Interval, StopOrderStatus};
+use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
-use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Mutex;
-
-static ORDERBOOK: Lazy<Mutex<OrderBook>> = Lazy::new(|| {
-    Mutex::new(OrderBook {
-        limits: HashMap::new(),
-        timestamp: Utc::now(),
-    })
-});
-
-static TRADING_RECORDS: Lazy<Mutex<Vec<Record>>> = Lazy::new(|| {
-    let v = Vec::new();
-    Mutex::new(v)
-});
-
-static INSTRUMENT: Lazy<Mutex<Instrument>> = Lazy::new(|| Mutex::new(Default::default()));
-static POSITION: Lazy<Mutex<Position>> = Lazy::new(|| Mutex::new(Default::default()));
-
-fn orderbook(res: WebSocketResponse) {
-    let mut orderbook = ORDERBOOK.lock().expect("Failed to lock Mutex<HashMap>");
-    match res.msg_type.unwrap().as_str() {
-        "snapshot" => {
-            orderbook.timestamp = res.timestamp.unwrap();
-            if let Value::Array(data) = res.data {
-                data.into_iter().for_each(|p| {
-                    orderbook.limits.insert(
-                        p.get("id").unwrap().as_u64().unwrap(),
-                        serde_json::from_value::<Limit>(p)
-                            .expect("Failed to deserialize response data"),
-                    );
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+/// Local state built from a stream of [`WsEvent`]s.
+///
+/// [`WebSocket::run_forever`](super::WebSocket::run_forever) feeds the process-wide
+/// store returned by [`global`]; applications consuming the event stream themselves
+/// can keep their own `Store` or none at all.
+#[derive(Clone, Debug, Default)]
+pub struct Store {
+    orderbooks: HashMap<(OrderBookDepth, String), OrderBook>,
+    trading_records: Vec<Record>,
+    instruments: HashMap<String, Instrument>,
+    positions: HashMap<String, Position>,
+    candles: HashMap<(String, Interval), CandleSeries>,
+    insurance: HashMap<String, VecDeque<Insurance>>,
+    liquidations: HashMap<String, VecDeque<Liquidation>>,
+    fills: HashMap<String, VecDeque<Execution>>,
+    orders: HashMap<String, Order>,
+    order_link_ids: HashMap<String, String>,
+    stop_orders: HashMap<String, StopOrder>,
+    triggered_stop_orders: VecDeque<StopOrder>,
+    wallets: HashMap<String, VecDeque<Wallet>>,
+    tickers: HashMap<String, Ticker>,
+    resyncs: HashMap<(OrderBookDepth, String), usize>,
+}
+
+/// Wallet updates kept per coin, enough for an intraday equity curve.
+const MAX_WALLET_RECORDS: usize = 10_000;
+
+/// Insurance fund balances kept per currency.
+const MAX_INSURANCE_RECORDS: usize = 2000;
+
+/// Liquidations kept per symbol, enough to follow a cascade.
+const MAX_LIQUIDATION_RECORDS: usize = 5000;
+
+/// Fills kept per symbol until taken; the oldest go first.
+const MAX_FILL_RECORDS: usize = 10_000;
+
+/// Triggered conditional orders kept until taken.
+const MAX_TRIGGERED_STOP_ORDERS: usize = 1000;
+
+static STORE: Lazy<Mutex<Store>> = Lazy::new(|| Mutex::new(Store::default()));
+
+impl Store {
+    pub fn apply(&mut self, event: &WsEvent) {
+        match event {
+            WsEvent::OrderBookSnapshot {
+                depth,
+                symbol,
+                cross_seq,
+                timestamp,
+                limits,
+            } => {
+                let orderbook = self.orderbooks.entry((*depth, symbol.clone())).or_default();
+                set_timestamp(orderbook, timestamp);
+                orderbook.cross_seq = *cross_seq;
+                orderbook.healthy = true;
+                orderbook.limits.clear();
+                store_limits(orderbook, limits);
+            }
+            WsEvent::OrderBookDelta {
+                depth,
+                symbol,
+                cross_seq,
+                timestamp,
+                delete,
+                update,
+                insert,
+            } => {
+                // Deltas only apply on top of a snapshot.
+                let orderbook = match self.orderbooks.get_mut(&(*depth, symbol.clone())) {
+                    Some(orderbook) if orderbook.healthy => orderbook,
+                    _ => return,
+                };
+                set_timestamp(orderbook, timestamp);
+                orderbook.cross_seq = cross_seq.or(orderbook.cross_seq);
+                delete.iter().for_each(|limit| {
+                    orderbook.limits.remove(&limit.id);
                 });
+                store_limits(orderbook, update);
+                store_limits(orderbook, insert);
+            }
+            WsEvent::OrderBookGap { depth, symbol, .. } => {
+                let key = (*depth, symbol.clone());
+                if let Some(orderbook) = self.orderbooks.get_mut(&key) {
+                    orderbook.healthy = false;
+                }
+                *self.resyncs.entry(key).or_default() += 1;
             }
+            WsEvent::Trade(records) => self.trading_records.extend(records.iter().cloned()),
+            WsEvent::InstrumentSnapshot {
+                symbol, instrument, ..
+            } => {
+                self.instruments.insert(symbol.clone(), instrument.clone());
+            }
+            WsEvent::InstrumentDelta {
+                symbol, updates, ..
+            } => {
+                let instrument = self.instruments.entry(symbol.clone()).or_default();
+                updates.iter().for_each(|update| instrument.update(update));
+            }
+            WsEvent::KLine {
+                symbol,
+                interval,
+                candles,
+            } => {
+                let series = self.candles.entry((symbol.clone(), *interval)).or_default();
+                candles
+                    .iter()
+                    .for_each(|candle| series.push(candle.clone()));
+            }
+            WsEvent::Insurance(records) => records.iter().for_each(|record| {
+                let series = self.insurance.entry(record.currency.clone()).or_default();
+                match series.back_mut() {
+                    Some(last) if last.timestamp == record.timestamp => *last = record.clone(),
+                    Some(last) if last.timestamp > record.timestamp => {}
+                    _ => series.push_back(record.clone()),
+                }
+                if series.len() > MAX_INSURANCE_RECORDS {
+                    series.pop_front();
+                }
+            }),
+            WsEvent::Liquidation(liquidations) => liquidations.iter().for_each(|liquidation| {
+                let series = self
+                    .liquidations
+                    .entry(liquidation.symbol.clone())
+                    .or_default();
+                series.push_back(liquidation.clone());
+                if series.len() > MAX_LIQUIDATION_RECORDS {
+                    series.pop_front();
+                }
+            }),
+            WsEvent::Execution(executions) => executions.iter().for_each(|execution| {
+                let fills = self.fills.entry(execution.symbol.clone()).or_default();
+                fills.push_back(execution.clone());
+                if fills.len() > MAX_FILL_RECORDS {
+                    fills.pop_front();
+                }
+            }),
+            WsEvent::Order(orders) => orders.iter().for_each(|order| self.store_order(order)),
+            WsEvent::StopOrder(stop_orders) => stop_orders
+                .iter()
+                .for_each(|stop_order| self.store_stop_order(stop_order)),
+            WsEvent::Wallet(wallets) => wallets.iter().for_each(|wallet| {
+                let series = self.wallets.entry(wallet.coin.clone()).or_default();
+                series.push_back(wallet.clone());
+                if series.len() > MAX_WALLET_RECORDS {
+                    series.pop_front();
+                }
+            }),
+            WsEvent::Ticker(tickers) => tickers.iter().for_each(|ticker| {
+                self.tickers.insert(ticker.symbol.clone(), ticker.clone());
+            }),
+            WsEvent::Position(positions) => positions.iter().for_each(|position| {
+                self.positions
+                    .insert(position.symbol.clone(), position.clone());
+            }),
+            WsEvent::Ack {
+                success: true,
+                op,
+                args,
+                ..
+            } if op == "unsubscribe" => args.iter().for_each(|arg| {
+                if let Some((topic, symbol)) = Topic::parse(arg) {
+                    self.remove(topic, symbol.as_deref());
+                }
+            }),
+            WsEvent::Reconnected => self.reset(),
+            _ => {}
+        }
+    }
+
+    /// Each depth subscribed to is a book of its own.
+    pub fn orderbook(&self, depth: OrderBookDepth, symbol: &str) -> Option<&OrderBook> {
+        self.orderbooks.get(&(depth, symbol.to_owned()))
+    }
+
+    /// Whether the `depth` orderbook of `symbol` is built from a snapshot and every
+    /// delta since, in sequence.
+    pub fn is_healthy(&self, depth: OrderBookDepth, symbol: &str) -> bool {
+        self.orderbook(depth, symbol)
+            .is_some_and(|orderbook| orderbook.healthy)
+    }
+
+    /// How many times the `depth` orderbook of `symbol` had to be resynced.
+    pub fn resyncs(&self, depth: OrderBookDepth, symbol: &str) -> usize {
+        self.resyncs
+            .get(&(depth, symbol.to_owned()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn orderbooks(&self) -> &HashMap<(OrderBookDepth, String), OrderBook> {
+        &self.orderbooks
+    }
+
+    pub fn trading_records(&self) -> &[Record] {
+        &self.trading_records
+    }
+
+    pub fn take_trading_records(&mut self) -> Vec<Record> {
+        self.trading_records.drain(..).collect()
+    }
+
+    pub fn instrument(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(symbol)
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    /// The latest 24 hour statistics of the spot `symbol`.
+    pub fn ticker(&self, symbol: &str) -> Option<&Ticker> {
+        self.tickers.get(symbol)
+    }
+
+    pub fn candles(&self, symbol: &str, interval: Interval) -> Option<&CandleSeries> {
+        self.candles.get(&(symbol.to_owned(), interval))
+    }
+
+    /// The fills of `symbol` not yet taken, in arrival order.
+    pub fn fills(&self, symbol: &str) -> Option<&VecDeque<Execution>> {
+        self.fills.get(symbol)
+    }
+
+    /// Removes and returns the fills of `symbol`, so each fill is consumed once.
+    pub fn take_fills(&mut self, symbol: &str) -> Vec<Execution> {
+        self.fills.remove(symbol).map(Vec::from).unwrap_or_default()
+    }
+
+    /// The open order with `order_id`.
+    pub fn order(&self, order_id: &str) -> Option<&Order> {
+        self.orders.get(order_id)
+    }
+
+    /// The open order with the client-side `order_link_id`.
+    pub fn order_by_link_id(&self, order_link_id: &str) -> Option<&Order> {
+        self.order_link_ids
+            .get(order_link_id)
+            .and_then(|order_id| self.orders.get(order_id))
+    }
+
+    /// The open orders on `symbol`, oldest update first.
+    pub fn open_orders(&self, symbol: &str) -> Vec<&Order> {
+        let mut orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| order.symbol == symbol)
+            .collect();
+        orders.sort_by_key(|order| order.timestamp);
+        orders
+    }
+
+    /// The conditional orders on `symbol` still waiting for their trigger price.
+    pub fn stop_orders(&self, symbol: &str) -> Vec<&StopOrder> {
+        let mut stop_orders: Vec<&StopOrder> = self
+            .stop_orders
+            .values()
+            .filter(|stop_order| stop_order.symbol == symbol)
+            .collect();
+        stop_orders.sort_by_key(|stop_order| stop_order.timestamp);
+        stop_orders
+    }
+
+    /// Removes and returns the conditional orders triggered since the last call.
+    pub fn take_triggered_stop_orders(&mut self) -> Vec<StopOrder> {
+        self.triggered_stop_orders.drain(..).collect()
+    }
+
+    /// The latest balance of `coin`.
+    pub fn wallet(&self, coin: &str) -> Option<&Wallet> {
+        self.wallets.get(coin).and_then(VecDeque::back)
+    }
+
+    /// The balance updates of `coin`, oldest first.
+    pub fn wallet_history(&self, coin: &str) -> Option<&VecDeque<Wallet>> {
+        self.wallets.get(coin)
+    }
+
+    /// The insurance fund balances of `currency`, oldest first.
+    pub fn insurance(&self, currency: &str) -> Option<&VecDeque<Insurance>> {
+        self.insurance.get(currency)
+    }
+
+    /// How far the latest insurance fund balance of `currency` is below the highest
+    /// one kept, as a fraction of that high: `0.0` at a high, `0.1` after losing 10%.
+    pub fn insurance_drawdown(&self, currency: &str) -> Option<f64> {
+        let series = self.insurance.get(currency)?;
+        let peak = series.iter().map(|r| r.wallet_balance).max()?;
+        let latest = series.back()?.wallet_balance;
+
+        match peak {
+            0 => Some(0.0),
+            _ => Some((peak - latest) as f64 / peak as f64),
         }
-        "delta" => {
-            orderbook.timestamp = res.timestamp.unwrap();
-            if let Value::Object(data) = res.data {
-                data.get("delete")
-                    .unwrap()
-                    .as_array()
-                    .unwrap()
-                    .into_iter()
-                    .for_each(|p| {
-                        orderbook
-                            .limits
-                            .remove(&p.get("id").unwrap().as_u64().unwrap());
-                    });
-                data.get("update")
-                    .unwrap()
-                    .as_array()
-                    .unwrap()
-                    .into_iter()
-                    .for_each(|p| {
-                        orderbook.limits.insert(
-                            p.get("id").unwrap().as_u64().unwrap(),
-                            serde_json::from_value::<Limit>(p.clone())
-                                .expect("Failed to deserialize response data"),
-                        );
-                    });
-                data.get("insert")
-                    .unwrap()
-                    .as_array()
-                    .unwrap()
-                    .into_iter()
-                    .for_each(|p| {
-                        orderbook.limits.insert(
-                            p.get("id").unwrap().as_u64().unwrap(),
-                            serde_json::from_value::<Limit>(p.clone())
-                                .expect("Failed to deserialize response data"),
-                        );
-                    });
+    }
+
+    /// The latest liquidations of `symbol`, oldest first.
+    pub fn liquidations(&self, symbol: &str) -> Option<&VecDeque<Liquidation>> {
+        self.liquidations.get(symbol)
+    }
+
+    /// Drops everything stored for `symbol`.
+    pub fn remove_symbol(&mut self, symbol: &str) {
+        self.orderbooks.retain(|(_, s), _| s != symbol);
+        self.trading_records.retain(|r| r.symbol != symbol);
+        self.instruments.remove(symbol);
+        self.positions.remove(symbol);
+        self.tickers.remove(symbol);
+        self.liquidations.remove(symbol);
+        self.candles.retain(|(s, _), _| s != symbol);
+        self.fills.remove(symbol);
+        self.remove_orders(|order| order.symbol == symbol);
+        self.stop_orders
+            .retain(|_, stop_order| stop_order.symbol != symbol);
+        self.triggered_stop_orders
+            .retain(|stop_order| stop_order.symbol != symbol);
+    }
+
+    /// Drops what `topic` stored for `symbol`, or for every symbol given `None` or `*`.
+    pub fn remove(&mut self, topic: Topic, symbol: Option<&str>) {
+        let symbol = symbol.filter(|s| *s != "*");
+        match topic {
+            Topic::OrderBook(depth) => self
+                .orderbooks
+                .retain(|(d, s), _| *d != depth || symbol.is_some_and(|symbol| s != symbol)),
+            Topic::Trade => self
+                .trading_records
+                .retain(|r| symbol.is_some_and(|s| r.symbol != s)),
+            Topic::Ticker => match symbol {
+                Some(symbol) => {
+                    self.tickers.remove(symbol);
+                }
+                None => self.tickers.clear(),
+            },
+            Topic::Instrument(_) => match symbol {
+                Some(symbol) => {
+                    self.instruments.remove(symbol);
+                }
+                None => self.instruments.clear(),
+            },
+            Topic::KLine(interval) => self
+                .candles
+                .retain(|(s, i), _| *i != interval || symbol.is_some_and(|symbol| s != symbol)),
+            Topic::Liquidation => match symbol {
+                Some(symbol) => {
+                    self.liquidations.remove(symbol);
+                }
+                None => self.liquidations.clear(),
+            },
+            Topic::Insurance => match symbol {
+                Some(currency) => {
+                    self.insurance.remove(currency);
+                }
+                None => self.insurance.clear(),
+            },
+            Topic::Position => self.positions.clear(),
+            Topic::Execution => self.fills.clear(),
+            Topic::Order => self.remove_orders(|_| true),
+            Topic::Wallet => self.wallets.clear(),
+            Topic::StopOrder => {
+                self.stop_orders.clear();
+                self.triggered_stop_orders.clear();
             }
         }
-        _ => panic!("Impossible message type"),
     }
-}
 
-fn records(res: WebSocketResponse) {
-    let mut records = TRADING_RECORDS
-        .lock()
-        .expect("Failed to lock Mutex<Vec<Record>>");
+    /// Updates come with the whole order; terminal ones leave the table and stale
+    /// ones, older than what is stored, are ignored.
+    fn store_order(&mut self, order: &Order) {
+        if let Some(stored) = self.orders.get(&order.order_id) {
+            if stored.timestamp > order.timestamp {
+                return;
+            }
+        }
 
-    if let Value::Array(data) = res.data {
-        data.into_iter().for_each(|r| {
-            records
-                .push(serde_json::from_value::<Record>(r).expect("Failed to deserialize record"));
-        })
+        if order.order_status.is_terminal() {
+            self.orders.remove(&order.order_id);
+            self.order_link_ids.remove(&order.order_link_id);
+        } else {
+            if !order.order_link_id.is_empty() {
+                self.order_link_ids
+                    .insert(order.order_link_id.clone(), order.order_id.clone());
+            }
+            self.orders.insert(order.order_id.clone(), order.clone());
+        }
     }
-}
 
-fn instrument(res: WebSocketResponse) {
-    let mut instrument = INSTRUMENT.lock().expect("Failed to lock Mutex<Instrument>");
-    match res.msg_type.unwrap().as_str() {
-        "snapshot" => {
-            *instrument = serde_json::from_value::<Instrument>(res.data)
-                .expect("Failed to deserialize snapshot of instrument_info");
+    fn store_stop_order(&mut self, stop_order: &StopOrder) {
+        if let Some(stored) = self.stop_orders.get(&stop_order.order_id) {
+            if stored.timestamp > stop_order.timestamp {
+                return;
+            }
+        }
+
+        if stop_order.order_status.is_pending() {
+            self.stop_orders
+                .insert(stop_order.order_id.clone(), stop_order.clone());
+            return;
         }
-        "delta" => {
-            if let Value::Object(data) = res.data {
-                *instrument =
-                    serde_json::from_value::<Instrument>(data.get("update").unwrap().clone())
-                        .expect("Failed to deserialize delta of instrument_info");
+        self.stop_orders.remove(&stop_order.order_id);
+        if stop_order.order_status == StopOrderStatus::Triggered {
+            self.triggered_stop_orders.push_back(stop_order.clone());
+            if self.triggered_stop_orders.len() > MAX_TRIGGERED_STOP_ORDERS {
+                self.triggered_stop_orders.pop_front();
             }
         }
-        _ => panic!("Impossible message type"),
+    }
+
+    fn remove_orders<F>(&mut self, f: F)
+    where
+        F: Fn(&Order) -> bool,
+    {
+        let orders = &mut self.orders;
+        orders.retain(|_, order| !f(order));
+        self.order_link_ids
+            .retain(|_, order_id| orders.contains_key(order_id));
+    }
+
+    /// Drops state that is only valid relative to the previous connection.
+    /// Orderbook deltas apply to the snapshot sent on (re)subscription, and orders
+    /// and positions may have changed while disconnected; private topics only push
+    /// changes, so these stay empty until the next update or a REST query.
+    pub fn reset(&mut self) {
+        self.orderbooks.clear();
+        self.remove_orders(|_| true);
+        self.stop_orders.clear();
+        self.positions.clear();
     }
 }
 
-fn position(res: WebSocketResponse) {
-    let mut position = POSITION.lock().expect("Failed to lock Mutex<Position>");
-    *position =
-        serde_json::from_value::<Position>(res.data).expect("Failed to deserialize position");
+fn store_limits(orderbook: &mut OrderBook, limits: &[Limit]) {
+    limits.iter().for_each(|limit| {
+        orderbook.limits.insert(limit.id, limit.clone());
+    });
 }
 
-pub(crate) fn store_message(res: WebSocketResponse) {
-    match res.topic.chars().next() {
-        Some('o') if res.topic.chars().nth(5).is_some() => orderbook(res), // orderbook
-        Some('t') => records(res),                                         // trade
-        Some('i') if res.topic.chars().nth(10).is_none() => todo!(),       // insurance
-        Some('i') => instrument(res),                                      // instrument_info
-        Some('k') => todo!(),                                              // kline
-        Some('p') => position(res),                                        // position
-        Some('e') => todo!(),                                              // execution
-        Some('o') => todo!(),                                              // order
-        Some('s') => todo!(),                                              // stop_order
-        _ => unreachable!(),
-    }
-
-    // if res.topic.starts_with("orderBook") {
-    //     store_orderbook(res);
-    // } else if res.topic.starts_with("trade") {
-    //     store_records(res);
-    // } else if res.topic.starts_with("instrument_info") {
-    //     todo!();
-    // } else if res.topic.starts_with("kline") {
-    //     todo!();
-    // } else if res.topic.starts_with("position") {
-    //     todo!();
-    // } else if res.topic.starts_with("execution") {
-    //     todo!();
-    // } else if res.topic.starts_with("order") {
-    //     todo!();
-    // } else if res.topic.starts_with("stop_order") {
-    //     todo!();
-    // }
+fn set_timestamp(orderbook: &mut OrderBook, timestamp: &Option<DateTime<Utc>>) {
+    if let Some(timestamp) = timestamp {
+        orderbook.timestamp = *timestamp;
+    }
+}
+
+/// The process-wide store fed by [`WebSocket::run_forever`](super::WebSocket::run_forever).
+pub fn global() -> MutexGuard<'static, Store> {
+    STORE.lock().expect("Failed to lock Mutex<Store>")
 }
 
-pub fn take_orderbook() -> OrderBook {
-    ORDERBOOK.lock().unwrap().clone()
+pub fn take_orderbook(depth: OrderBookDepth, symbol: &str) -> Option<OrderBook> {
+    global().orderbook(depth, symbol).cloned()
 }
 
 pub fn take_trading_records() -> Vec<Record> {
-    TRADING_RECORDS.lock().unwrap().drain(..).collect()
+    global().take_trading_records()
+}
+
+pub fn take_fills(symbol: &str) -> Vec<Execution> {
+    global().take_fills(symbol)
 }
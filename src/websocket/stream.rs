@@ -0,0 +1,154 @@
+use super::core::WebSocket;
+use super::event::WsEvent;
+use super::subscription::{SubscribeResult, Subscription};
+use crate::runtime;
+
+use async_tungstenite::tungstenite::{Error, Result};
+use futures::{
+    channel::{mpsc, oneshot},
+    pin_mut, select,
+    stream::Stream,
+    FutureExt, SinkExt, StreamExt,
+};
+use log::{info, warn};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Events read ahead of the [`EventStream`] before the socket waits for it.
+const EVENT_BUFFER: usize = 1024;
+
+/// The events of a [`WebSocket`], see [`WebSocket::next_event`].
+pub struct EventStream {
+    events: mpsc::Receiver<Result<WsEvent>>,
+}
+
+impl Stream for EventStream {
+    type Item = Result<WsEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+/// Subscribes, unsubscribes and closes a [`WebSocket`] turned into an
+/// [`EventStream`], see [`WebSocket::into_stream`].
+///
+/// Acks read meanwhile are also passed on to the stream. Once the socket is gone,
+/// every call fails with [`Error::ConnectionClosed`].
+#[derive(Clone)]
+pub struct WebSocketWriter {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+enum Command {
+    Subscribe(Vec<Subscription>, oneshot::Sender<Result<SubscribeResult>>),
+    Unsubscribe(Vec<Subscription>, oneshot::Sender<Result<SubscribeResult>>),
+    Close(oneshot::Sender<Result<()>>),
+}
+
+impl WebSocketWriter {
+    /// See [`WebSocket::subscribe_to`].
+    pub async fn subscribe_to<S>(&self, subscriptions: S) -> Result<SubscribeResult>
+    where
+        S: IntoIterator<Item = Subscription>,
+    {
+        let subscriptions = subscriptions.into_iter().collect();
+        self.request(|reply| Command::Subscribe(subscriptions, reply))
+            .await
+    }
+
+    /// See [`WebSocket::unsubscribe_from`].
+    pub async fn unsubscribe_from<S>(&self, subscriptions: S) -> Result<SubscribeResult>
+    where
+        S: IntoIterator<Item = Subscription>,
+    {
+        let subscriptions = subscriptions.into_iter().collect();
+        self.request(|reply| Command::Unsubscribe(subscriptions, reply))
+            .await
+    }
+
+    /// See [`WebSocket::close`]; the stream ends once its remaining events are read.
+    pub async fn close(&self) -> Result<()> {
+        self.request(Command::Close).await
+    }
+
+    async fn request<T, F>(&self, command: F) -> Result<T>
+    where
+        F: FnOnce(oneshot::Sender<Result<T>>) -> Command,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.commands
+            .unbounded_send(command(reply_tx))
+            .map_err(|_| Error::ConnectionClosed)?;
+        reply_rx.await.unwrap_or(Err(Error::ConnectionClosed))
+    }
+}
+
+/// Spawns a task reading `ws` into the stream and running the writer's commands
+/// in between events.
+pub(super) fn split(mut ws: WebSocket) -> (EventStream, WebSocketWriter) {
+    let (mut events_tx, events_rx) = mpsc::channel(EVENT_BUFFER);
+    let (commands_tx, commands_rx) = mpsc::unbounded();
+
+    runtime::spawn(async move {
+        let mut commands = commands_rx.fuse();
+        loop {
+            let event = select! {
+                event = ws.next_event().fuse() => event,
+                command = commands.next() => {
+                    if let Some(command) = command {
+                        run(&mut ws, command).await;
+                    }
+                    continue;
+                }
+            };
+            let event = match event {
+                Some(event) => event,
+                None => break,
+            };
+
+            // Commands keep running while the stream is full.
+            let send = events_tx.send(event).fuse();
+            pin_mut!(send);
+            let sent = loop {
+                select! {
+                    sent = send => break sent,
+                    command = commands.next() => {
+                        if let Some(command) = command {
+                            run(&mut ws, command).await;
+                        }
+                    }
+                }
+            };
+            if sent.is_err() {
+                if let Err(err) = ws.close().await {
+                    warn!("Failed to close websocket: {}", err);
+                }
+                break;
+            }
+        }
+
+        info!("Event stream ended");
+    });
+
+    (
+        EventStream { events: events_rx },
+        WebSocketWriter {
+            commands: commands_tx,
+        },
+    )
+}
+
+async fn run(ws: &mut WebSocket, command: Command) {
+    match command {
+        Command::Subscribe(subscriptions, reply) => {
+            let _ = reply.send(ws.subscribe_to(subscriptions).await);
+        }
+        Command::Unsubscribe(subscriptions, reply) => {
+            let _ = reply.send(ws.unsubscribe_from(subscriptions).await);
+        }
+        Command::Close(reply) => {
+            let _ = reply.send(ws.close().await);
+        }
+    }
+}
@@ -1,17 +1,31 @@
-mod instrument;
+mod execution;
+pub(crate) mod instrument;
+mod insurance;
+mod kline;
+mod liquidation;
+mod order;
 mod orderbook;
 mod position;
 mod record;
+pub(crate) mod spot;
+mod wallet;
 
-pub use instrument::Instrument;
+pub use execution::Execution;
+pub use instrument::{Instrument, InstrumentUpdate};
+pub use insurance::Insurance;
+pub use kline::{Candle, CandleSeries};
+pub use liquidation::Liquidation;
+pub use order::{Order, StopOrder};
 pub use orderbook::{Limit, OrderBook};
 pub use position::Position;
 pub use record::Record;
+pub use spot::Ticker;
+pub use wallet::Wallet;
 
 use async_tungstenite::tungstenite::protocol::Message;
-use serde::{self, Serialize};
+use serde::{self, Deserialize, Deserializer, Serialize};
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Clone, Debug)]
 pub(crate) struct WsArgs {
     pub op: String,
     pub args: Option<Vec<String>>,
@@ -23,6 +37,82 @@ impl WsArgs {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrNumber {
+    String(String),
+    Integer(u64),
+    Number(f64),
+}
+
+/// Bybit sends most decimals as strings, but some topics (and linear contracts)
+/// send plain numbers, and linear contracts send integers as strings; all are
+/// accepted.
+pub(crate) fn deserialize_string_to_f32<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_string_to_f64(deserializer).map(|v| v as f32)
+}
+
+pub(crate) fn deserialize_string_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(s) if s.is_empty() => Ok(0.0),
+        StringOrNumber::String(s) => s.parse::<f64>().map_err(serde::de::Error::custom),
+        StringOrNumber::Integer(n) => Ok(n as f64),
+        StringOrNumber::Number(n) => Ok(n),
+    }
+}
+
+pub(crate) fn deserialize_string_to_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(s) if s.is_empty() => Ok(0),
+        StringOrNumber::String(s) => s.parse::<u64>().map_err(serde::de::Error::custom),
+        StringOrNumber::Integer(n) => Ok(n),
+        StringOrNumber::Number(n) => Ok(n as u64),
+    }
+}
+
+pub(crate) fn deserialize_option_string_to_u64<'de, D>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_string_to_u64(deserializer).map(Some)
+}
+
+/// For enums implementing `Display` and `FromStr` with the exchange's spelling.
+pub(crate) mod serde_from_str {
+    use serde::{self, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 pub(crate) mod serde_side {
     use crate::common::enums::Side;
     use serde::{self, Deserialize, Deserializer, Serializer};
@@ -35,7 +125,7 @@ pub(crate) mod serde_side {
             Side::Buy => "Buy",
             Side::Sell => "Sell",
         };
-        serializer.serialize_str(&s)
+        serializer.serialize_str(s)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Side, D::Error>
@@ -46,7 +136,10 @@ pub(crate) mod serde_side {
         match s.as_str() {
             "Buy" => Ok(Side::Buy),
             "Sell" => Ok(Side::Sell),
-            _ => panic!("Impossible order side"),
+            _ => Err(serde::de::Error::custom(format!(
+                "Impossible order side: {}",
+                s
+            ))),
         }
     }
 }
@@ -65,7 +158,7 @@ pub(crate) mod serde_tick {
             Tick::PlusTick => "PlusTick",
             Tick::ZeroPlusTick => "ZeroPlusTick",
         };
-        serializer.serialize_str(&s)
+        serializer.serialize_str(s)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Tick, D::Error>
@@ -78,7 +171,45 @@ pub(crate) mod serde_tick {
             "ZeroMinusTick" => Ok(Tick::ZeroMinusTick),
             "PlusTick" => Ok(Tick::PlusTick),
             "ZeroPlusTick" => Ok(Tick::ZeroPlusTick),
-            _ => panic!("Impossible tick direction"),
+            _ => Err(serde::de::Error::custom(format!(
+                "Impossible tick direction: {}",
+                s
+            ))),
+        }
+    }
+}
+
+pub(crate) mod serde_trigger {
+    use crate::websocket::enums::Trigger;
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(side: &Trigger, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let s = match side {
+            Trigger::LastPrice => "LastPrice",
+            Trigger::IndexPrice => "IndexPrice",
+            Trigger::MarkPrice => "MarkPrice",
+            Trigger::None => "None",
+        };
+        serializer.serialize_str(s)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Trigger, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        match s.as_str() {
+            "LastPrice" => Ok(Trigger::LastPrice),
+            "IndexPrice" => Ok(Trigger::IndexPrice),
+            "MarkPrice" => Ok(Trigger::MarkPrice),
+            "" | "None" => Ok(Trigger::None),
+            _ => Err(serde::de::Error::custom(format!(
+                "Impossible trigger: {}",
+                s
+            ))),
         }
     }
 }
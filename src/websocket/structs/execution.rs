@@ -0,0 +1,27 @@
+use super::{deserialize_string_to_f32, deserialize_string_to_f64, serde_side};
+use crate::common::enums::Side;
+use chrono::{DateTime, Utc};
+use serde::{self, Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Execution {
+    pub symbol: String,
+    #[serde(with = "serde_side")]
+    pub side: Side,
+    pub order_id: String,
+    pub exec_id: String,
+    pub order_link_id: String,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub price: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub order_qty: f64,
+    pub exec_type: String,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub exec_qty: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub exec_fee: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub leaves_qty: f64,
+    pub is_maker: bool,
+    pub trade_time: DateTime<Utc>,
+}
@@ -1,8 +1,9 @@
-use super::serde_tick;
+use super::{deserialize_string_to_f64, serde_tick};
 use crate::websocket::enums::Tick;
 use serde::{self, Deserialize, Deserializer};
+use serde_json::{Map, Value};
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Default, Clone, Debug)]
 pub struct Instrument {
     pub symbol: String,
     #[serde(rename(deserialize = "last_price_e4"))]
@@ -34,7 +35,8 @@ pub struct Instrument {
     #[serde(rename(deserialize = "index_price_e4"))]
     #[serde(deserialize_with = "deserialize_e4")]
     pub index_price: f32,
-    pub open_interest: usize,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub open_interest: f64,
     // #[serde(rename(deserialize = "open_value_e8"))]
     // open_value: usize, // open position value * 10^8
     #[serde(rename(deserialize = "total_turnover_e8"))]
@@ -43,8 +45,10 @@ pub struct Instrument {
     #[serde(rename(deserialize = "turnover_24h_e8"))]
     #[serde(deserialize_with = "deserialize_e8")]
     pub turnover_24h: f64,
-    pub total_volume: usize,
-    pub volume_24h: usize,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub total_volume: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub volume_24h: f64,
     #[serde(rename(deserialize = "predicted_funding_rate_e6"))]
     #[serde(deserialize_with = "deserialize_e6")]
     pub predicted_funding_rate: f32,
@@ -54,26 +58,200 @@ pub struct Instrument {
     pub countdown_hour: u8,
 }
 
+/// The changed fields of an `instrument_info` delta; absent fields are unchanged.
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct InstrumentUpdate {
+    pub symbol: String,
+    #[serde(default)]
+    #[serde(rename(deserialize = "last_price_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub last_price: Option<f32>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_option_tick")]
+    pub last_tick_direction: Option<Tick>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "prev_price_24h_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub prev_price_24h: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "price_24h_pcnt_e6"))]
+    #[serde(deserialize_with = "deserialize_option_e6")]
+    pub price_24h_pcnt: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "high_price_24h_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub high_price_24h: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "low_price_24h_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub low_price_24h: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "prev_price_1h_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub prev_price_1h: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "price_1h_pcnt_e6"))]
+    #[serde(deserialize_with = "deserialize_option_e6")]
+    pub price_1h_pcnt: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "mark_price_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub mark_price: Option<f32>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "index_price_e4"))]
+    #[serde(deserialize_with = "deserialize_option_e4")]
+    pub index_price: Option<f32>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_option_f64")]
+    pub open_interest: Option<f64>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "total_turnover_e8"))]
+    #[serde(deserialize_with = "deserialize_option_e8")]
+    pub total_turnover: Option<f64>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "turnover_24h_e8"))]
+    #[serde(deserialize_with = "deserialize_option_e8")]
+    pub turnover_24h: Option<f64>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_option_f64")]
+    pub total_volume: Option<f64>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_option_f64")]
+    pub volume_24h: Option<f64>,
+    #[serde(default)]
+    #[serde(rename(deserialize = "predicted_funding_rate_e6"))]
+    #[serde(deserialize_with = "deserialize_option_e6")]
+    pub predicted_funding_rate: Option<f32>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub next_funding_time: Option<String>,
+    pub countdown_hour: Option<u8>,
+}
+
+impl Instrument {
+    pub fn update(&mut self, update: &InstrumentUpdate) {
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                $(if let Some(value) = &update.$field {
+                    self.$field = value.clone();
+                })*
+            };
+        }
+
+        self.symbol = update.symbol.clone();
+        apply!(
+            last_price,
+            last_tick_direction,
+            prev_price_24h,
+            price_24h_pcnt,
+            high_price_24h,
+            low_price_24h,
+            prev_price_1h,
+            price_1h_pcnt,
+            mark_price,
+            index_price,
+            open_interest,
+            total_turnover,
+            turnover_24h,
+            total_volume,
+            volume_24h,
+            predicted_funding_rate,
+            created_at,
+            updated_at,
+            next_funding_time,
+            countdown_hour
+        );
+    }
+}
+
 fn deserialize_e4<'de, D>(deserializer: D) -> Result<f32, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s = i32::deserialize(deserializer)?;
-    Ok(s as f32 / 10f32.powi(4))
+    let s = deserialize_string_to_f64(deserializer)?;
+    Ok((s / 10f64.powi(4)) as f32)
 }
 
 fn deserialize_e6<'de, D>(deserializer: D) -> Result<f32, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s = i32::deserialize(deserializer)?;
-    Ok(s as f32 / 10f32.powi(6))
+    let s = deserialize_string_to_f64(deserializer)?;
+    Ok((s / 10f64.powi(6)) as f32)
 }
 
 fn deserialize_e8<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s = i64::deserialize(deserializer)?;
-    Ok(s as f64 / 10f64.powi(8))
+    let s = deserialize_string_to_f64(deserializer)?;
+    Ok(s / 10f64.powi(8))
+}
+
+fn deserialize_option_e4<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_e4(deserializer).map(Some)
+}
+
+fn deserialize_option_e6<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_e6(deserializer).map(Some)
+}
+
+fn deserialize_option_e8<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_e8(deserializer).map(Some)
+}
+
+fn deserialize_option_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_string_to_f64(deserializer).map(Some)
+}
+
+fn deserialize_option_tick<'de, D>(deserializer: D) -> Result<Option<Tick>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    serde_tick::deserialize(deserializer).map(Some)
+}
+
+/// Linear contracts scale volumes by 10^8 and spell `count_down_hour`; rewrites
+/// those keys, anywhere in `data`, to the inverse ones.
+pub(crate) fn normalize(data: &mut Value) {
+    match data {
+        Value::Object(object) => {
+            normalize_object(object);
+            object.values_mut().for_each(normalize);
+        }
+        Value::Array(values) => values.iter_mut().for_each(normalize),
+        _ => {}
+    }
+}
+
+fn normalize_object(object: &mut Map<String, Value>) {
+    for key in &["open_interest", "total_volume", "volume_24h"] {
+        if let Some(value) = object.remove(&format!("{}_e8", key)) {
+            let value = number(&value).map(|n| n / 10f64.powi(8));
+            object.insert(key.to_string(), value.into());
+        }
+    }
+    if let Some(value) = object.remove("count_down_hour") {
+        let hours = number(&value).map(|n| n as u64);
+        object.insert("countdown_hour".to_owned(), hours.into());
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        value => value.as_f64(),
+    }
 }
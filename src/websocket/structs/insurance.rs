@@ -0,0 +1,9 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Insurance {
+    pub currency: String,
+    pub timestamp: DateTime<Utc>,
+    pub wallet_balance: u64,
+}
@@ -0,0 +1,120 @@
+use super::{deserialize_string_to_f32, deserialize_string_to_f64};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{self, Deserialize, Deserializer, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Candle {
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub start: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub end: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub open: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub close: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub high: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub low: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub volume: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub turnover: f64,
+    /// `false` while the candle is still open.
+    pub confirm: bool,
+    #[serde(default)]
+    pub cross_seq: u64,
+    #[serde(deserialize_with = "deserialize_micros")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Candles of one symbol and interval: confirmed bars, oldest first, and the bar
+/// still forming.
+#[derive(Clone, Debug)]
+pub struct CandleSeries {
+    confirmed: VecDeque<Candle>,
+    forming: Option<Candle>,
+    capacity: usize,
+}
+
+impl Default for CandleSeries {
+    fn default() -> Self {
+        CandleSeries::new(1000)
+    }
+}
+
+impl CandleSeries {
+    /// Keeps at most `capacity` confirmed bars, dropping the oldest.
+    pub fn new(capacity: usize) -> Self {
+        CandleSeries {
+            confirmed: VecDeque::new(),
+            forming: None,
+            capacity,
+        }
+    }
+
+    pub fn confirmed(&self) -> &VecDeque<Candle> {
+        &self.confirmed
+    }
+
+    pub fn forming(&self) -> Option<&Candle> {
+        self.forming.as_ref()
+    }
+
+    /// An unconfirmed bar replaces the forming one, which is confirmed first if the
+    /// new bar starts later, as spot klines are never confirmed.
+    pub fn push(&mut self, candle: Candle) {
+        if !candle.confirm {
+            if let Some(mut forming) = self.forming.take() {
+                if forming.start < candle.start {
+                    forming.confirm = true;
+                    self.push(forming);
+                }
+            }
+            self.forming = Some(candle);
+            return;
+        }
+
+        if self
+            .forming
+            .as_ref()
+            .is_some_and(|f| f.start <= candle.start)
+        {
+            self.forming = None;
+        }
+        match self.confirmed.back_mut() {
+            Some(last) if last.start == candle.start => *last = candle,
+            Some(last) if last.start > candle.start => {}
+            _ => {
+                self.confirmed.push_back(candle);
+                if self.confirmed.len() > self.capacity {
+                    self.confirmed.pop_front();
+                }
+            }
+        }
+    }
+}
+
+fn deserialize_seconds<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = i64::deserialize(deserializer)?;
+    Utc.timestamp_opt(s, 0)
+        .single()
+        .ok_or_else(|| serde::de::Error::custom(format!("Timestamp out of range: {}s", s)))
+}
+
+fn deserialize_micros<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = i64::deserialize(deserializer)?;
+    Utc.timestamp_opt(
+        s.div_euclid(1_000_000),
+        s.rem_euclid(1_000_000) as u32 * 1_000,
+    )
+    .single()
+    .ok_or_else(|| serde::de::Error::custom(format!("Timestamp out of range: {}us", s)))
+}
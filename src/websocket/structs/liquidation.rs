@@ -0,0 +1,20 @@
+use super::record::serde_record_timestamp;
+use super::{deserialize_string_to_f32, deserialize_string_to_f64, serde_side};
+use crate::common::enums::Side;
+use chrono::{DateTime, Utc};
+use serde::{self, Deserialize, Serialize};
+
+/// A forced liquidation; `side` is that of the liquidated position's closing order.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Liquidation {
+    pub symbol: String,
+    #[serde(with = "serde_side")]
+    pub side: Side,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub price: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub qty: f64,
+    #[serde(rename(deserialize = "time"))]
+    #[serde(with = "serde_record_timestamp")]
+    pub timestamp: DateTime<Utc>,
+}
@@ -0,0 +1,106 @@
+use super::{
+    deserialize_string_to_f32, deserialize_string_to_f64, deserialize_string_to_u64,
+    serde_from_str, serde_side, serde_trigger,
+};
+use crate::common::enums::Side;
+use crate::rest::{OrderStatus, OrderType, StopOrderStatus, StopOrderType, TimeInForce};
+use crate::websocket::enums::Trigger;
+use chrono::{DateTime, Utc};
+use serde::{self, Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Order {
+    pub order_id: String,
+    pub order_link_id: String,
+    pub symbol: String,
+    #[serde(with = "serde_side")]
+    pub side: Side,
+    #[serde(with = "serde_from_str")]
+    pub order_type: OrderType,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub price: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub qty: f64,
+    #[serde(with = "serde_from_str")]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub create_type: String,
+    #[serde(default)]
+    pub cancel_type: String,
+    #[serde(with = "serde_from_str")]
+    pub order_status: OrderStatus,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub leaves_qty: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub cum_exec_qty: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub cum_exec_value: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub cum_exec_fee: f64,
+    /// `update_time` for linear contracts.
+    #[serde(alias = "update_time")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub take_profit: f32,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub stop_loss: f32,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub trailing_stop: f32,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub last_exec_price: f32,
+    #[serde(default)]
+    pub reduce_only: bool,
+    #[serde(default)]
+    pub close_on_trigger: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StopOrder {
+    #[serde(alias = "stop_order_id")]
+    pub order_id: String,
+    pub order_link_id: String,
+    #[serde(deserialize_with = "deserialize_string_to_u64")]
+    pub user_id: u64,
+    pub symbol: String,
+    #[serde(with = "serde_side")]
+    pub side: Side,
+    #[serde(with = "serde_from_str")]
+    pub order_type: OrderType,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub price: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub qty: f64,
+    #[serde(with = "serde_from_str")]
+    pub time_in_force: TimeInForce,
+    #[serde(default)]
+    pub create_type: String,
+    #[serde(default)]
+    pub cancel_type: String,
+    #[serde(with = "serde_from_str")]
+    pub order_status: StopOrderStatus,
+    /// Absent for linear contracts.
+    #[serde(default)]
+    #[serde(with = "serde_from_str")]
+    pub stop_order_type: StopOrderType,
+    #[serde(default)]
+    #[serde(with = "serde_trigger")]
+    pub trigger_by: Trigger,
+    #[serde(rename(deserialize = "trigger_price"))]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub stop_px: f32,
+    #[serde(default)]
+    pub close_on_trigger: bool,
+    /// `update_time` for linear contracts.
+    #[serde(alias = "update_time")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub take_profit: f32,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub stop_loss: f32,
+}
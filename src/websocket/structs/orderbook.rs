@@ -1,28 +1,33 @@
-use super::serde_side;
+use super::{
+    deserialize_string_to_f32, deserialize_string_to_f64, deserialize_string_to_u64, serde_side,
+};
 use crate::common::Side;
 use chrono::{DateTime, Utc};
-use serde::{self, Deserialize, Deserializer};
+use serde::{self, Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct OrderBook {
     pub limits: HashMap<u64, Limit>,
     pub timestamp: DateTime<Utc>,
+    /// The `cross_seq` of the last snapshot or delta applied.
+    pub cross_seq: Option<u64>,
+    /// Cleared by a [`WsEvent::OrderBookGap`](crate::websocket::WsEvent::OrderBookGap)
+    /// until the next snapshot.
+    pub healthy: bool,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Limit {
-    #[serde(deserialize_with = "deserialize_price")]
+    #[serde(deserialize_with = "deserialize_string_to_u64")]
+    pub id: u64,
+    pub symbol: String,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub price: f32,
     #[serde(with = "serde_side")]
     pub side: Side,
-    pub size: u32,
-}
-
-fn deserialize_price<'de, D>(deserializer: D) -> Result<f32, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    Ok(s.parse::<f32>().unwrap())
+    /// Contracts for inverse, coins for linear. Absent (zero) in delete deltas.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub size: f64,
 }
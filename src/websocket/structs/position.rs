@@ -1,15 +1,22 @@
-use super::serde_side;
+use super::{
+    deserialize_string_to_f32, deserialize_string_to_f64, deserialize_string_to_u64, serde_side,
+    serde_trigger,
+};
 use crate::common::enums::Side;
 use crate::websocket::enums::{Status, Trigger};
-use serde::{self, Deserialize, Deserializer, Serialize};
+use serde::{self, Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Default)]
+/// Fields absent from linear positions, e.g. `wallet_balance`, are left at zero.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
 pub struct Position {
-    pub user_id: usize,
+    #[serde(deserialize_with = "deserialize_string_to_u64")]
+    pub user_id: u64,
     pub symbol: String,
     #[serde(with = "serde_side")]
     pub side: Side,
-    pub size: usize,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub size: f64,
     #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub position_value: f32,
     #[serde(deserialize_with = "deserialize_string_to_f32")]
@@ -18,12 +25,12 @@ pub struct Position {
     pub liq_price: f32,
     #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub bust_price: f32,
-    #[serde(deserialize_with = "deserialize_string_to_u8")]
-    pub leverage: u8,
-    #[serde(deserialize_with = "deserialize_string_to_u8")]
-    pub order_margin: u8,
-    #[serde(deserialize_with = "deserialize_string_to_u8")]
-    pub position_margin: u8,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub leverage: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub order_margin: f32,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub position_margin: f32,
     #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub available_balance: f32,
     #[serde(deserialize_with = "deserialize_string_to_f32")]
@@ -43,6 +50,7 @@ pub struct Position {
     #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub wallet_balance: f32,
     pub risk_id: usize,
+    #[serde(alias = "isolated")]
     pub is_isolated: bool,
     #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub occ_closing_fee: f32,
@@ -56,52 +64,6 @@ pub struct Position {
     // pub position_seq: usize,
 }
 
-fn deserialize_string_to_f32<'de, D>(deserializer: D) -> Result<f32, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    Ok(s.parse::<f32>().unwrap())
-}
-
-fn deserialize_string_to_u8<'de, D>(deserializer: D) -> Result<u8, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    Ok(s.parse::<u8>().unwrap())
-}
-
-mod serde_trigger {
-    use crate::websocket::enums::Trigger;
-    use serde::{self, Deserialize, Deserializer, Serializer};
-
-    pub fn serialize<S>(side: &Trigger, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let s = match side {
-            Trigger::LastPrice => "LastPrice",
-            Trigger::IndexPrice => "IndexPrice",
-            Trigger::None => "None",
-        };
-        serializer.serialize_str(&s)
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Trigger, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        match s.as_str() {
-            "LastPrice" => Ok(Trigger::LastPrice),
-            "IndexPrice" => Ok(Trigger::IndexPrice),
-            "" => Ok(Trigger::None),
-            _ => panic!("Impossible order side"),
-        }
-    }
-}
-
 mod serde_status {
     use crate::websocket::enums::Status;
     use serde::{self, Deserialize, Deserializer, Serializer};
@@ -115,7 +77,7 @@ mod serde_status {
             Status::Liquidation => "Liquidation",
             Status::AutoDeleveraging => "Auto-Deleveraging",
         };
-        serializer.serialize_str(&s)
+        serializer.serialize_str(s)
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Status, D::Error>
@@ -127,7 +89,10 @@ mod serde_status {
             "Normal" => Ok(Status::Normal),
             "Liq" => Ok(Status::Liquidation),
             "Adl" => Ok(Status::AutoDeleveraging),
-            _ => panic!("Impossible order side"),
+            _ => Err(serde::de::Error::custom(format!(
+                "Impossible position status: {}",
+                s
+            ))),
         }
     }
 }
@@ -1,26 +1,35 @@
-use super::{serde_side, serde_tick};
+use super::{
+    deserialize_string_to_f32, deserialize_string_to_f64, deserialize_string_to_u64, serde_side,
+    serde_tick,
+};
 use crate::common::enums::Side;
 use crate::websocket::enums::Tick;
 use chrono::{DateTime, Utc};
 use serde::{self, Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Record {
     pub trade_id: String,
+    pub symbol: String,
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
     pub price: f32,
     #[serde(with = "serde_side")]
     pub side: Side,
-    pub size: u32,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub size: f64,
     #[serde(rename(deserialize = "trade_time_ms"))]
     #[serde(with = "serde_record_timestamp")]
     pub timestamp: DateTime<Utc>,
     #[serde(with = "serde_tick")]
     pub tick_direction: Tick,
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_u64")]
+    pub cross_seq: u64,
 }
 
-mod serde_record_timestamp {
+pub(super) mod serde_record_timestamp {
     use chrono::{DateTime, TimeZone, Utc};
-    use serde::{self, Deserialize, Deserializer, Serializer};
+    use serde::{self, Deserializer, Serializer};
 
     pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -34,7 +43,7 @@ mod serde_record_timestamp {
     where
         D: Deserializer<'de>,
     {
-        let s = i64::deserialize(deserializer)?;
-        Ok(Utc.timestamp_millis(s))
+        let s = super::super::deserialize_string_to_u64(deserializer)? as i64;
+        Ok(Utc.timestamp_millis_opt(s).unwrap())
     }
 }
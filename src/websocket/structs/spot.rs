@@ -0,0 +1,369 @@
+use super::{
+    deserialize_string_to_f32, deserialize_string_to_f64, deserialize_string_to_u64, Candle,
+    Execution, Limit, Order, Record, Wallet,
+};
+use crate::common::enums::Side;
+use crate::rest::{Interval, OrderStatus, OrderType, TimeInForce};
+use crate::websocket::enums::Tick;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{self, Deserialize, Deserializer, Serialize};
+
+/// 24 hour statistics of a spot symbol, from `realtimes`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ticker {
+    #[serde(rename(deserialize = "s"))]
+    pub symbol: String,
+    #[serde(rename(deserialize = "t"))]
+    #[serde(deserialize_with = "deserialize_millis")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename(deserialize = "o"))]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub open: f32,
+    #[serde(rename(deserialize = "h"))]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub high: f32,
+    #[serde(rename(deserialize = "l"))]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub low: f32,
+    /// The last traded price.
+    #[serde(rename(deserialize = "c"))]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub close: f32,
+    #[serde(rename(deserialize = "v"))]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub volume: f64,
+    #[serde(rename(deserialize = "qv"))]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub quote_volume: f64,
+    /// Change since 24 hours ago, e.g. `0.01` for 1%.
+    #[serde(rename(deserialize = "m"))]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    pub change: f32,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpotTrade {
+    #[serde(rename = "v")]
+    trade_id: String,
+    #[serde(rename = "t")]
+    #[serde(deserialize_with = "deserialize_millis")]
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "p")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    price: f32,
+    #[serde(rename = "q")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    size: f64,
+    #[serde(rename = "m")]
+    buyer_is_maker: bool,
+}
+
+impl SpotTrade {
+    pub(crate) fn into_record(self, symbol: &str) -> Record {
+        Record {
+            trade_id: self.trade_id,
+            symbol: symbol.to_owned(),
+            price: self.price,
+            // The side of the taker.
+            side: if self.buyer_is_maker {
+                Side::Sell
+            } else {
+                Side::Buy
+            },
+            size: self.size,
+            timestamp: self.timestamp,
+            tick_direction: Tick::default(),
+            cross_seq: 0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpotDepth {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "t")]
+    #[serde(deserialize_with = "deserialize_millis")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "b")]
+    bids: Vec<[String; 2]>,
+    #[serde(rename = "a")]
+    asks: Vec<[String; 2]>,
+}
+
+impl SpotDepth {
+    /// Spot levels have no id, so the price scaled by 10^8 stands in for one.
+    pub(crate) fn limits(&self) -> Result<Vec<Limit>, std::num::ParseFloatError> {
+        let bids = self.bids.iter().map(|level| (Side::Buy, level));
+        let asks = self.asks.iter().map(|level| (Side::Sell, level));
+        bids.chain(asks)
+            .map(|(side, [price, size])| {
+                let price = price.parse::<f64>()?;
+                Ok(Limit {
+                    id: (price * 10f64.powi(8)).round() as u64,
+                    symbol: self.symbol.clone(),
+                    price: price as f32,
+                    side,
+                    size: size.parse()?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpotKline {
+    #[serde(rename = "t")]
+    #[serde(deserialize_with = "deserialize_millis")]
+    start: DateTime<Utc>,
+    #[serde(rename = "o")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    open: f32,
+    #[serde(rename = "c")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    close: f32,
+    #[serde(rename = "h")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    high: f32,
+    #[serde(rename = "l")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    low: f32,
+    #[serde(rename = "v")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    volume: f64,
+}
+
+impl SpotKline {
+    /// Spot klines are never confirmed; a bar is complete once the next one starts.
+    pub(crate) fn into_candle(self, interval: Interval) -> Candle {
+        Candle {
+            start: self.start,
+            end: self.start + kline_duration(interval),
+            open: self.open,
+            close: self.close,
+            high: self.high,
+            low: self.low,
+            volume: self.volume,
+            turnover: 0.0,
+            confirm: false,
+            cross_seq: 0,
+            timestamp: self.start,
+        }
+    }
+}
+
+/// `outboundAccountInfo`, the balances of the spot account.
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpotAccount {
+    #[serde(rename = "E")]
+    #[serde(deserialize_with = "deserialize_millis")]
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "B")]
+    balances: Vec<SpotBalance>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SpotBalance {
+    #[serde(rename = "a")]
+    coin: String,
+    #[serde(rename = "f")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    free: f64,
+    #[serde(rename = "l")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    locked: f64,
+}
+
+impl SpotAccount {
+    pub(crate) fn into_wallets(self) -> impl Iterator<Item = Wallet> {
+        let timestamp = self.timestamp;
+        self.balances.into_iter().map(move |balance| Wallet {
+            user_id: 0,
+            coin: balance.coin,
+            wallet_balance: balance.free + balance.locked,
+            available_balance: balance.free,
+            timestamp,
+        })
+    }
+}
+
+/// `executionReport`, the whole order after each change.
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpotExecutionReport {
+    #[serde(rename = "E")]
+    #[serde(deserialize_with = "deserialize_millis")]
+    timestamp: DateTime<Utc>,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "c")]
+    order_link_id: String,
+    #[serde(rename = "S")]
+    #[serde(deserialize_with = "deserialize_side")]
+    side: Side,
+    #[serde(rename = "o")]
+    order_type: String,
+    #[serde(rename = "f")]
+    time_in_force: String,
+    #[serde(rename = "q")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    qty: f64,
+    #[serde(rename = "p")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    price: f32,
+    #[serde(rename = "X")]
+    #[serde(deserialize_with = "deserialize_status")]
+    order_status: OrderStatus,
+    #[serde(rename = "i")]
+    order_id: String,
+    #[serde(rename = "z")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    cum_exec_qty: f64,
+    #[serde(rename = "Z")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    cum_exec_value: f64,
+    #[serde(rename = "L")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    last_exec_price: f32,
+}
+
+impl From<SpotExecutionReport> for Order {
+    fn from(report: SpotExecutionReport) -> Self {
+        let (order_type, time_in_force) = match (&*report.order_type, &*report.time_in_force) {
+            ("MARKET", _) => (OrderType::Market, TimeInForce::ImmediateOrCancel),
+            ("LIMIT_MAKER", _) => (OrderType::Limit, TimeInForce::PostOnly),
+            (_, "FOK") => (OrderType::Limit, TimeInForce::FillOrKill),
+            (_, "IOC") => (OrderType::Limit, TimeInForce::ImmediateOrCancel),
+            _ => (OrderType::Limit, TimeInForce::GoodTillCancel),
+        };
+
+        Order {
+            order_id: report.order_id,
+            order_link_id: report.order_link_id,
+            symbol: report.symbol,
+            side: report.side,
+            order_type,
+            price: report.price,
+            qty: report.qty,
+            time_in_force,
+            create_type: String::new(),
+            cancel_type: String::new(),
+            order_status: report.order_status,
+            leaves_qty: report.qty - report.cum_exec_qty,
+            cum_exec_qty: report.cum_exec_qty,
+            cum_exec_value: report.cum_exec_value,
+            // Spot only reports the fee of the latest fill.
+            cum_exec_fee: 0.0,
+            timestamp: report.timestamp,
+            take_profit: 0.0,
+            stop_loss: 0.0,
+            trailing_stop: 0.0,
+            last_exec_price: report.last_exec_price,
+            reduce_only: false,
+            close_on_trigger: false,
+        }
+    }
+}
+
+/// `ticketInfo`, one fill.
+#[derive(Deserialize, Debug)]
+pub(crate) struct SpotTicket {
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "S")]
+    #[serde(deserialize_with = "deserialize_side")]
+    side: Side,
+    #[serde(rename = "q")]
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    qty: f64,
+    #[serde(rename = "t")]
+    #[serde(deserialize_with = "deserialize_millis")]
+    trade_time: DateTime<Utc>,
+    #[serde(rename = "p")]
+    #[serde(deserialize_with = "deserialize_string_to_f32")]
+    price: f32,
+    #[serde(rename = "T")]
+    exec_id: String,
+    #[serde(rename = "o")]
+    order_id: String,
+    #[serde(rename = "c")]
+    order_link_id: String,
+    #[serde(rename = "m")]
+    is_maker: bool,
+}
+
+impl From<SpotTicket> for Execution {
+    /// A ticket carries neither the fee nor the order quantity; both are left at zero.
+    fn from(ticket: SpotTicket) -> Self {
+        Execution {
+            symbol: ticket.symbol,
+            side: ticket.side,
+            order_id: ticket.order_id,
+            exec_id: ticket.exec_id,
+            order_link_id: ticket.order_link_id,
+            price: ticket.price,
+            order_qty: 0.0,
+            exec_type: String::from("Trade"),
+            exec_qty: ticket.qty,
+            exec_fee: 0.0,
+            leaves_qty: 0.0,
+            is_maker: ticket.is_maker,
+            trade_time: ticket.trade_time,
+        }
+    }
+}
+
+fn kline_duration(interval: Interval) -> Duration {
+    match interval {
+        Interval::OneMin => Duration::minutes(1),
+        Interval::ThreeMin => Duration::minutes(3),
+        Interval::FiveMin => Duration::minutes(5),
+        Interval::FifteenMin => Duration::minutes(15),
+        Interval::ThirtyMin => Duration::minutes(30),
+        Interval::OneHour => Duration::hours(1),
+        Interval::TwoHour => Duration::hours(2),
+        Interval::FourHour => Duration::hours(4),
+        Interval::SixHour => Duration::hours(6),
+        Interval::TwelveHour => Duration::hours(12),
+        Interval::Day => Duration::days(1),
+        Interval::Week => Duration::weeks(1),
+        Interval::Month => Duration::days(30),
+    }
+}
+
+fn deserialize_millis<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = deserialize_string_to_u64(deserializer)? as i64;
+    Ok(Utc.timestamp_millis_opt(s).unwrap())
+}
+
+fn deserialize_side<'de, D>(deserializer: D) -> Result<Side, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "BUY" => Ok(Side::Buy),
+        "SELL" => Ok(Side::Sell),
+        s => Err(serde::de::Error::custom(format!("Unknown side: {}", s))),
+    }
+}
+
+fn deserialize_status<'de, D>(deserializer: D) -> Result<OrderStatus, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "PENDING_NEW" => Ok(OrderStatus::Created),
+        "NEW" => Ok(OrderStatus::New),
+        "PARTIALLY_FILLED" => Ok(OrderStatus::PartiallyFilled),
+        "FILLED" => Ok(OrderStatus::Filled),
+        "PENDING_CANCEL" => Ok(OrderStatus::PendingCancel),
+        "CANCELED" => Ok(OrderStatus::Cancelled),
+        "REJECTED" => Ok(OrderStatus::Rejected),
+        s => Err(serde::de::Error::custom(format!(
+            "Unknown order status: {}",
+            s
+        ))),
+    }
+}
@@ -0,0 +1,25 @@
+use super::{deserialize_string_to_f64, deserialize_string_to_u64};
+use chrono::{DateTime, Utc};
+use serde::{self, Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Wallet {
+    /// Absent for linear contracts.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_string_to_u64")]
+    pub user_id: u64,
+    /// Linear contracts only have a USDT wallet and leave the coin out.
+    #[serde(default = "usdt")]
+    pub coin: String,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub wallet_balance: f64,
+    #[serde(deserialize_with = "deserialize_string_to_f64")]
+    pub available_balance: f64,
+    /// Wallet updates carry no time of their own; this is when one was received.
+    #[serde(default = "Utc::now")]
+    pub timestamp: DateTime<Utc>,
+}
+
+fn usdt() -> String {
+    String::from("USDT")
+}
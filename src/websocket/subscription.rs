@@ -0,0 +1,270 @@
+use super::enums::{Channel, Topic};
+use crate::rest::Interval;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Symbols {
+    /// `*`, every symbol the topic is published for.
+    All,
+    Only(Vec<String>),
+}
+
+/// One topic with its own symbols, see
+/// [`WebSocket::subscribe_to`](super::WebSocket::subscribe_to).
+///
+/// ```no_run
+/// # use bybit_rs::prelude::{Symbol, Topic};
+/// # use bybit_rs::rest::Interval;
+/// # use bybit_rs::websocket::Subscription;
+/// let subscriptions = vec![
+///     Subscription::new(Topic::Trade).symbols(vec![Symbol::BTCUSD, Symbol::ETHUSD]),
+///     Subscription::kline(Interval::FiveMin).all(),
+///     Subscription::orderbook(200).symbol(Symbol::BTCUSD),
+///     Subscription::new(Topic::Position),
+/// ];
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscription {
+    pub topic: Topic,
+    pub symbols: Symbols,
+}
+
+impl Subscription {
+    /// Subscribes to no symbol until [`symbol`](Self::symbol), [`symbols`](Self::symbols)
+    /// or [`all`](Self::all) is called, which is what private topics expect.
+    pub fn new(topic: Topic) -> Self {
+        Subscription {
+            topic,
+            symbols: Symbols::Only(Vec::new()),
+        }
+    }
+
+    /// See [`Topic::orderbook`].
+    pub fn orderbook(depth: u16) -> Self {
+        Subscription::new(Topic::orderbook(depth))
+    }
+
+    pub fn kline(interval: Interval) -> Self {
+        Subscription::new(Topic::KLine(interval))
+    }
+
+    pub fn symbol<S: Into<String>>(mut self, symbol: S) -> Self {
+        match &mut self.symbols {
+            Symbols::All => {}
+            Symbols::Only(symbols) => symbols.push(symbol.into()),
+        }
+        self
+    }
+
+    pub fn symbols<I, S>(self, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        symbols.into_iter().fold(self, Subscription::symbol)
+    }
+
+    pub fn all(mut self) -> Self {
+        self.symbols = Symbols::All;
+        self
+    }
+
+    /// The `subscribe` arguments on `channel`, one per symbol.
+    pub(crate) fn args(&self, channel: Channel) -> Vec<String> {
+        if channel == Channel::SpotPrivate {
+            return Vec::new();
+        }
+
+        let prefix = self.topic.prefix_on(channel);
+
+        match &self.symbols {
+            Symbols::All => vec![format!("{}.*", prefix)],
+            Symbols::Only(symbols) if symbols.is_empty() => vec![prefix],
+            Symbols::Only(symbols) => symbols
+                .iter()
+                .map(|symbol| format!("{}.{}", prefix, symbol))
+                .collect(),
+        }
+    }
+}
+
+/// The outcome of [`WebSocket::subscribe_to`](super::WebSocket::subscribe_to) or
+/// [`WebSocket::unsubscribe_from`](super::WebSocket::unsubscribe_from), per argument.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubscribeResult {
+    pub succeeded: Vec<String>,
+    /// Each rejected argument with the exchange's `ret_msg`.
+    pub failed: Vec<(String, String)>,
+}
+
+impl SubscribeResult {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AckStatus {
+    Pending,
+    Acked,
+    Rejected,
+}
+
+/// One `subscribe` argument, e.g. `trade.BTCUSD`, as currently registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActiveSubscription {
+    pub arg: String,
+    pub topic: Topic,
+    /// `None` for account-wide topics, `*` for wildcards.
+    pub symbol: Option<String>,
+    pub status: AckStatus,
+}
+
+/// The subscriptions of a [`WebSocket`](super::WebSocket), shared with the handle
+/// returned by [`WebSocket::registry`](super::WebSocket::registry) so it can be
+/// queried after the socket has been turned into a stream.
+#[derive(Clone, Debug, Default)]
+pub struct SubscriptionRegistry {
+    inner: Arc<Mutex<Vec<ActiveSubscription>>>,
+}
+
+impl SubscriptionRegistry {
+    pub fn active(&self) -> Vec<ActiveSubscription> {
+        self.lock().clone()
+    }
+
+    pub fn status(&self, arg: &str) -> Option<AckStatus> {
+        self.lock().iter().find(|s| s.arg == arg).map(|s| s.status)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// The arguments to replay after a reconnect; rejected ones are left out.
+    pub(crate) fn args(&self) -> Vec<String> {
+        self.lock()
+            .iter()
+            .filter(|s| s.status != AckStatus::Rejected)
+            .map(|s| s.arg.clone())
+            .collect()
+    }
+
+    pub(crate) fn insert(&self, args: &[String]) {
+        let mut subscriptions = self.lock();
+        args.iter().for_each(
+            |arg| match subscriptions.iter_mut().find(|s| &s.arg == arg) {
+                Some(subscription) => subscription.status = AckStatus::Pending,
+                None => {
+                    if let Some((topic, symbol)) = Topic::parse(arg) {
+                        subscriptions.push(ActiveSubscription {
+                            arg: arg.clone(),
+                            topic,
+                            symbol,
+                            status: AckStatus::Pending,
+                        });
+                    }
+                }
+            },
+        );
+    }
+
+    pub(crate) fn remove(&self, args: &[String]) {
+        self.lock().retain(|s| !args.contains(&s.arg));
+    }
+
+    pub(crate) fn set_status(&self, args: &[String], status: AckStatus) {
+        self.lock()
+            .iter_mut()
+            .filter(|s| args.contains(&s.arg))
+            .for_each(|s| s.status = status);
+    }
+
+    /// Everything is re-sent after a reconnect and awaits a new ack.
+    pub(crate) fn reset(&self) {
+        self.lock()
+            .iter_mut()
+            .filter(|s| s.status == AckStatus::Acked)
+            .for_each(|s| s.status = AckStatus::Pending);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<ActiveSubscription>> {
+        self.inner
+            .lock()
+            .expect("Failed to lock Mutex<SubscriptionRegistry>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AckStatus, Subscription, SubscriptionRegistry};
+    use crate::common::Symbol;
+    use crate::rest::Interval;
+    use crate::websocket::enums::{Channel, Topic};
+
+    #[test]
+    fn test_args() {
+        assert_eq!(
+            Subscription::new(Topic::Trade)
+                .symbols(vec![Symbol::BTCUSD, Symbol::ETHUSD])
+                .args(Channel::Inverse),
+            vec!["trade.BTCUSD", "trade.ETHUSD"]
+        );
+        assert_eq!(
+            Subscription::kline(Interval::Day)
+                .all()
+                .args(Channel::Inverse),
+            vec!["klineV2.D.*"]
+        );
+        assert_eq!(
+            Subscription::kline(Interval::FiveMin)
+                .symbol(Symbol::ETHUSD)
+                .args(Channel::Inverse),
+            vec!["klineV2.5.ETHUSD"]
+        );
+        assert_eq!(
+            Subscription::orderbook(200)
+                .symbol("BTCUSD")
+                .args(Channel::Inverse),
+            vec!["orderBook_200.100ms.BTCUSD"]
+        );
+        assert_eq!(
+            Subscription::new(Topic::Position).args(Channel::Inverse),
+            vec!["position"]
+        );
+        assert_eq!(
+            Subscription::kline(Interval::OneMin)
+                .symbol("BTCUSDT")
+                .args(Channel::LinearPublic),
+            vec!["candle.1.BTCUSDT"]
+        );
+        assert_eq!(
+            Subscription::kline(Interval::OneHour)
+                .symbol("BTCUSDT")
+                .args(Channel::SpotPublic),
+            vec!["kline.1h.BTCUSDT"]
+        );
+        assert!(Subscription::new(Topic::Order)
+            .args(Channel::SpotPrivate)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_registry() {
+        let registry = SubscriptionRegistry::default();
+        let args = Subscription::new(Topic::Trade)
+            .symbols(vec!["BTCUSD", "ETHUSD"])
+            .args(Channel::Inverse);
+
+        registry.insert(&args);
+        assert_eq!(registry.status("trade.BTCUSD"), Some(AckStatus::Pending));
+
+        registry.set_status(&args, AckStatus::Acked);
+        assert_eq!(registry.status("trade.ETHUSD"), Some(AckStatus::Acked));
+        assert_eq!(registry.active()[1].symbol.as_deref(), Some("ETHUSD"));
+
+        registry.remove(&args[..1]);
+        assert_eq!(registry.status("trade.BTCUSD"), None);
+        assert_eq!(registry.active().len(), 1);
+    }
+}
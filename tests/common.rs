@@ -5,5 +5,5 @@ pub type BEResult = Result<(), Box<dyn Error>>;
 
 pub fn init() {
     dotenv().ok();
-    pretty_env_logger::init();
+    let _ = pretty_env_logger::try_init();
 }
@@ -1,7 +1,9 @@
 extern crate bybit_rs;
 mod common;
-use bybit_rs::websocket::WebsocketResponse;
+use bybit_rs::rest::{ApiKeyInfo, Interval, RestResponse, ReturnCode};
+use bybit_rs::websocket::{WebSocketResponse, WsEvent};
 use log::debug;
+use std::convert::TryFrom;
 
 #[test]
 fn deserialize_response() -> common::BEResult {
@@ -28,10 +30,226 @@ fn deserialize_response() -> common::BEResult {
             "type": "snapshot"
         }"#;
 
-    let res: WebsocketResponse = serde_json::from_str(data)?;
+    let res: WebSocketResponse = serde_json::from_str(data)?;
     // let res: Value = serde_json::from_str(data).expect("Failed to deserialize JSON");
 
     debug!("{:#?}", res);
 
     Ok(())
 }
+
+#[test]
+fn deserialize_api_key_info() -> common::BEResult {
+    common::init();
+
+    let data = r#"
+        {
+            "ret_code": 0,
+            "ret_msg": "ok",
+            "ext_code": "",
+            "result": [{
+                "api_key": "7GkMBBLs5sNFiDxm4V",
+                "type": "personal",
+                "user_id": 1,
+                "inviter_id": 3,
+                "ips": ["*"],
+                "note": "scalper",
+                "permissions": ["Order", "Position"],
+                "created_at": "2020-10-28T10:13:49Z",
+                "expired_at": "2021-01-28T10:13:49Z",
+                "read_only": false
+            }],
+            "ext_info": "",
+            "time_now": "1604484543.616937",
+            "rate_limit_status": 119,
+            "rate_limit_reset_ms": 1604484543614,
+            "rate_limit": 120
+        }"#;
+
+    let res: RestResponse = serde_json::from_str(data)?;
+    let info: Vec<ApiKeyInfo> = res.into_result()?;
+    debug!("{:#?}", info);
+
+    assert_eq!(info[0].permissions, vec!["Order", "Position"]);
+    assert!(info[0].is_expired());
+    assert!(!info[0].is_ip_restricted());
+    assert!(!info[0].read_only);
+
+    let mut value = serde_json::from_str::<serde_json::Value>(data)?["result"][0].take();
+    value["expired_at"] = "".into();
+    value.as_object_mut().unwrap().remove("created_at");
+    let info: ApiKeyInfo = serde_json::from_value(value.clone())?;
+    assert_eq!(info.created_at, None);
+    assert_eq!(info.expired_at, None);
+    assert!(!info.is_expired());
+
+    value["expired_at"] = "2021-01-28".into();
+    assert!(serde_json::from_value::<ApiKeyInfo>(value).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn deserialize_api_key_expired() -> common::BEResult {
+    common::init();
+
+    let data = r#"
+        {
+            "ret_code": 33004,
+            "ret_msg": "api_key expire",
+            "ext_code": "",
+            "ext_info": "",
+            "result": null,
+            "time_now": "1641292871.629"
+        }"#;
+
+    let res: RestResponse = serde_json::from_str(data)?;
+    let err = res.into_result::<Vec<ApiKeyInfo>>().unwrap_err();
+
+    assert_eq!(err.return_code(), Some(ReturnCode::APIKeyExpired));
+
+    Ok(())
+}
+
+#[test]
+fn deserialize_private_events() -> common::BEResult {
+    common::init();
+
+    let execution = r#"
+        {
+            "topic": "execution",
+            "data": [{
+                "symbol": "BTCUSD",
+                "side": "Buy",
+                "order_id": "xxxxxxxx-xxxx-xxxx-9a8f-4a973eb5c418",
+                "exec_id": "xxxxxxxx-xxxx-xxxx-8b66-c3d2fcd352f6",
+                "order_link_id": "",
+                "price": "8300",
+                "order_qty": 1,
+                "exec_type": "Trade",
+                "exec_qty": 1,
+                "exec_fee": "0.00000009",
+                "leaves_qty": 0,
+                "is_maker": false,
+                "trade_time": "2020-01-14T14:07:23.629Z"
+            }]
+        }"#;
+    let wallet = r#"
+        {
+            "topic": "wallet",
+            "data": [{
+                "user_id": 738713,
+                "coin": "BTC",
+                "wallet_balance": 1.50121026,
+                "available_balance": 1.49925026
+            }]
+        }"#;
+
+    let res: WebSocketResponse = serde_json::from_str(execution)?;
+    match WsEvent::try_from(res)? {
+        WsEvent::Execution(executions) => {
+            debug!("{:#?}", executions);
+            assert_eq!(executions[0].exec_fee, 0.00000009);
+        }
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    let res: WebSocketResponse = serde_json::from_str(wallet)?;
+    assert!(matches!(WsEvent::try_from(res)?, WsEvent::Wallet(w) if w[0].coin == "BTC"));
+
+    Ok(())
+}
+
+#[test]
+fn deserialize_kline_event() -> common::BEResult {
+    common::init();
+
+    let data = r#"
+        {
+            "topic": "klineV2.1.BTCUSD",
+            "data": [{
+                "start": 1572425640,
+                "end": 1572425700,
+                "open": 9200,
+                "close": 9202.5,
+                "high": 9202.5,
+                "low": 9196,
+                "volume": 81790,
+                "turnover": 8.889247899999999,
+                "confirm": false,
+                "cross_seq": 297503466,
+                "timestamp": 1572425676958323
+            }],
+            "timestamp_e6": 1572425677047994
+        }"#;
+
+    let res: WebSocketResponse = serde_json::from_str(data)?;
+    match WsEvent::try_from(res)? {
+        WsEvent::KLine {
+            symbol,
+            interval,
+            candles,
+        } => {
+            assert_eq!(symbol, "BTCUSD");
+            assert_eq!(interval, Interval::OneMin);
+            assert_eq!(candles[0].close, 9202.5);
+            assert_eq!(candles[0].start.timestamp(), 1572425640);
+        }
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deserialize_linear_instrument_event() -> common::BEResult {
+    common::init();
+
+    let data = r#"
+        {
+            "topic": "instrument_info.100ms.BTCUSDT",
+            "type": "snapshot",
+            "data": {
+                "id": 1,
+                "symbol": "BTCUSDT",
+                "last_price_e4": "81165000",
+                "last_price": "8116.50",
+                "last_tick_direction": "ZeroPlusTick",
+                "prev_price_24h_e4": "81585000",
+                "price_24h_pcnt_e6": "-5148",
+                "high_price_24h_e4": "82900000",
+                "low_price_24h_e4": "79655000",
+                "prev_price_1h_e4": "81395000",
+                "price_1h_pcnt_e6": "-2825",
+                "mark_price_e4": "81178500",
+                "index_price_e4": "81172800",
+                "open_interest_e8": "154418471",
+                "total_turnover_e8": "4952400000000000",
+                "turnover_24h_e8": "2210400000000000",
+                "total_volume_e8": "61000000000",
+                "volume_24h_e8": "27100000000",
+                "funding_rate_e6": "100",
+                "predicted_funding_rate_e6": "100",
+                "cross_seq": "1053192657",
+                "created_at": "2020-01-01T00:00:00Z",
+                "updated_at": "2020-01-01T00:00:00Z",
+                "next_funding_time": "2020-01-01T08:00:00Z",
+                "count_down_hour": "8"
+            },
+            "cross_seq": "1053192657",
+            "timestamp_e6": "1587522536311536"
+        }"#;
+
+    let res: WebSocketResponse = serde_json::from_str(data)?;
+    assert_eq!(res.cross_seq, Some(1053192657));
+    match WsEvent::try_from(res)? {
+        WsEvent::InstrumentSnapshot { instrument, .. } => {
+            assert_eq!(instrument.last_price, 8116.5);
+            assert_eq!(instrument.volume_24h, 271.0);
+            assert_eq!(instrument.countdown_hour, 8);
+        }
+        event => panic!("Unexpected event: {:?}", event),
+    }
+
+    Ok(())
+}
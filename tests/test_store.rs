@@ -2,12 +2,555 @@
 mod common;
 
 extern crate bybit_rs;
-use bybit_rs::prelude::{Endpoint, WebsocketBuilder, WebsocketResponse, API};
-use bybit_rs::websocket::store;
+use bybit_rs::prelude::{Endpoint, WebSocketBuilder, WebSocketResponse, WsEvent, API};
+use bybit_rs::rest::{Interval, OrderStatus, StopOrderStatus, StopOrderType};
+use bybit_rs::websocket::store::{self, Store};
+use bybit_rs::websocket::{OrderBookDepth, Topic, Trigger};
 use log::{debug, info};
+use std::convert::TryFrom;
 use std::env;
 use tokio::time::{sleep, Duration};
 
+#[test]
+fn apply_orderbook_events() -> common::BEResult {
+    common::init();
+
+    let snapshot = r#"
+        {
+            "cross_seq": 5567735855,
+            "data": [{
+                "id": 543800000,
+                "price": "54380.00",
+                "side": "Buy",
+                "size": 3928,
+                "symbol": "BTCUSD"
+              }, {
+                "id": 545925000,
+                "price": "54592.50",
+                "side": "Sell",
+                "size": 18,
+                "symbol": "BTCUSD"
+            }],
+            "timestamp_e6": 1616855405687214,
+            "topic": "orderBook_200.100ms.BTCUSD",
+            "type": "snapshot"
+        }"#;
+    let delta = r#"
+        {
+            "topic": "orderBook_200.100ms.BTCUSD",
+            "type": "delta",
+            "data": {
+                "delete": [{
+                    "price": "54592.50",
+                    "symbol": "BTCUSD",
+                    "id": 545925000,
+                    "side": "Sell"
+                }],
+                "update": [{
+                    "price": "54380.00",
+                    "symbol": "BTCUSD",
+                    "id": 543800000,
+                    "side": "Buy",
+                    "size": 100
+                }],
+                "insert": [],
+                "transactTimeE6": 0
+            },
+            "cross_seq": 5567735856,
+            "timestamp_e6": 1616855405787214
+        }"#;
+
+    let depth = OrderBookDepth::new(200);
+    let mut store = Store::default();
+    store.apply(&parse(snapshot)?);
+    assert_eq!(store.orderbook(depth, "BTCUSD").unwrap().limits.len(), 2);
+
+    store.apply(&parse(delta)?);
+    let orderbook = store.orderbook(depth, "BTCUSD").unwrap();
+    debug!("{:#?}", orderbook);
+    assert_eq!(orderbook.limits.len(), 1);
+    assert_eq!(orderbook.limits[&543800000].size, 100.0);
+    assert_eq!(orderbook.cross_seq, Some(5567735856));
+    assert!(store.is_healthy(depth, "BTCUSD"));
+
+    store.apply(&WsEvent::OrderBookGap {
+        depth,
+        symbol: "BTCUSD".to_owned(),
+        last_seq: Some(5567735856),
+        cross_seq: Some(5567735850),
+    });
+    assert!(!store.is_healthy(depth, "BTCUSD"));
+    assert_eq!(store.resyncs(depth, "BTCUSD"), 1);
+
+    store.apply(&parse(snapshot)?);
+    assert!(store.is_healthy(depth, "BTCUSD"));
+    assert_eq!(store.orderbook(depth, "BTCUSD").unwrap().limits.len(), 2);
+
+    store.apply(&WsEvent::Reconnected);
+    assert!(store.orderbook(depth, "BTCUSD").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_trade_events() -> common::BEResult {
+    common::init();
+
+    let trade = r#"
+        {
+            "topic": "trade.BTCUSD",
+            "data": [{
+                "trade_time_ms": 1617705958803,
+                "timestamp": "2021-04-06T10:45:58.000Z",
+                "symbol": "BTCUSD",
+                "side": "Sell",
+                "size": 9460,
+                "price": 58617,
+                "tick_direction": "ZeroMinusTick",
+                "trade_id": "930ba5dd-67d3-5067-b253-117eb1aeeb7b",
+                "cross_seq": 5739533035
+            }]
+        }"#;
+
+    let mut store = Store::default();
+    store.apply(&parse(trade)?);
+    assert_eq!(store.trading_records().len(), 1);
+    assert_eq!(store.trading_records()[0].price, 58617.0);
+    assert_eq!(store.take_trading_records()[0].cross_seq, 5739533035);
+    assert!(store.trading_records().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn unsubscribe_removes_symbol() -> common::BEResult {
+    common::init();
+
+    let trade = |symbol: &str| {
+        format!(
+            r#"{{
+                "topic": "trade.{0}",
+                "data": [{{
+                    "trade_time_ms": 1617705958803,
+                    "timestamp": "2021-04-06T10:45:58.000Z",
+                    "symbol": "{0}",
+                    "side": "Sell",
+                    "size": 9460,
+                    "price": 58617,
+                    "tick_direction": "ZeroMinusTick",
+                    "trade_id": "930ba5dd-67d3-5067-b253-117eb1aeeb7b",
+                    "cross_seq": 5739533035
+                }}]
+            }}"#,
+            symbol
+        )
+    };
+
+    let mut store = Store::default();
+    store.apply(&parse(&trade("BTCUSD"))?);
+    store.apply(&parse(&trade("ETHUSD"))?);
+    assert_eq!(store.trading_records().len(), 2);
+
+    store.apply(&WsEvent::Ack {
+        success: true,
+        ret_msg: String::new(),
+        op: "unsubscribe".to_owned(),
+        args: vec!["trade.ETHUSD".to_owned()],
+    });
+    assert_eq!(store.trading_records().len(), 1);
+    assert_eq!(store.trading_records()[0].symbol, "BTCUSD");
+
+    Ok(())
+}
+
+#[test]
+fn apply_kline_events() -> common::BEResult {
+    common::init();
+
+    let kline = |start: i64, close: f32, confirm: bool| {
+        format!(
+            r#"{{
+                "topic": "klineV2.5.BTCUSD",
+                "data": [{{
+                    "start": {0},
+                    "end": {1},
+                    "open": 9200,
+                    "close": {2},
+                    "high": 9202.5,
+                    "low": 9196,
+                    "volume": 81790,
+                    "turnover": 8.889247899999999,
+                    "confirm": {3},
+                    "cross_seq": 297503466,
+                    "timestamp": 1572425676958323
+                }}]
+            }}"#,
+            start,
+            start + 300,
+            close,
+            confirm
+        )
+    };
+
+    let mut store = Store::default();
+    store.apply(&parse(&kline(1572425400, 9201.0, false))?);
+    store.apply(&parse(&kline(1572425400, 9202.5, true))?);
+    store.apply(&parse(&kline(1572425700, 9203.0, false))?);
+
+    let series = store.candles("BTCUSD", Interval::FiveMin).unwrap();
+    assert_eq!(series.confirmed().len(), 1);
+    assert_eq!(series.confirmed()[0].close, 9202.5);
+    assert_eq!(series.forming().unwrap().close, 9203.0);
+    assert!(store.candles("BTCUSD", Interval::OneMin).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_insurance_events() -> common::BEResult {
+    common::init();
+
+    let insurance = |timestamp: &str, balance: u64| {
+        format!(
+            r#"{{
+                "topic": "insurance.BTC",
+                "data": [{{
+                    "currency": "BTC",
+                    "timestamp": "{}",
+                    "wallet_balance": {}
+                }}]
+            }}"#,
+            timestamp, balance
+        )
+    };
+
+    let mut store = Store::default();
+    store.apply(&parse(&insurance("2020-01-14T00:00:00Z", 100_000))?);
+    store.apply(&parse(&insurance("2020-01-14T00:05:00Z", 120_000))?);
+    store.apply(&parse(&insurance("2020-01-14T00:10:00Z", 90_000))?);
+
+    assert_eq!(store.insurance("BTC").unwrap().len(), 3);
+    assert_eq!(store.insurance_drawdown("BTC"), Some(0.25));
+    assert!(store.insurance("ETH").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_liquidation_events() -> common::BEResult {
+    common::init();
+
+    let liquidation = r#"
+        {
+            "topic": "liquidation.BTCUSD",
+            "data": {
+                "symbol": "BTCUSD",
+                "side": "Sell",
+                "price": "33361.00",
+                "qty": "3.000",
+                "time": 1636526404018
+            }
+        }"#;
+
+    let mut store = Store::default();
+    store.apply(&parse(liquidation)?);
+    store.apply(&parse(liquidation)?);
+
+    let liquidations = store.liquidations("BTCUSD").unwrap();
+    assert_eq!(liquidations.len(), 2);
+    assert_eq!(liquidations[0].price, 33361.0);
+    assert_eq!(liquidations[0].qty, 3.0);
+    assert_eq!(liquidations[0].timestamp.timestamp_millis(), 1636526404018);
+
+    store.remove(Topic::Liquidation, Some("BTCUSD"));
+    assert!(store.liquidations("BTCUSD").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_execution_events() -> common::BEResult {
+    common::init();
+
+    let execution = r#"
+        {
+            "topic": "execution",
+            "data": [{
+                "symbol": "BTCUSD",
+                "side": "Buy",
+                "order_id": "xxxxxxxx-xxxx-xxxx-9a8f-4a973eb5c418",
+                "exec_id": "xxxxxxxx-xxxx-xxxx-8b66-c3d2fcd352f6",
+                "order_link_id": "hedge-1",
+                "price": "8300",
+                "order_qty": 1,
+                "exec_type": "Trade",
+                "exec_qty": 1,
+                "exec_fee": "0.00000009",
+                "leaves_qty": 0,
+                "is_maker": false,
+                "trade_time": "2020-01-14T14:07:23.629Z"
+            }]
+        }"#;
+
+    let mut store = Store::default();
+    store.apply(&parse(execution)?);
+    assert_eq!(store.fills("BTCUSD").unwrap().len(), 1);
+    assert!(store.fills("ETHUSD").is_none());
+
+    let fills = store.take_fills("BTCUSD");
+    assert_eq!(fills[0].order_link_id, "hedge-1");
+    assert!(store.fills("BTCUSD").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_order_events() -> common::BEResult {
+    common::init();
+
+    let order = |status: &str, cum_exec_qty: u32, timestamp: &str| {
+        format!(
+            r#"{{
+                "topic": "order",
+                "data": [{{
+                    "order_id": "xxxxxxxx-xxxx-xxxx-832b-1187e3a6ce6d",
+                    "order_link_id": "entry-1",
+                    "symbol": "BTCUSD",
+                    "side": "Sell",
+                    "order_type": "Limit",
+                    "price": "8579.5",
+                    "qty": 10,
+                    "time_in_force": "GoodTillCancel",
+                    "create_type": "CreateByUser",
+                    "cancel_type": "",
+                    "order_status": "{0}",
+                    "leaves_qty": {1},
+                    "cum_exec_qty": {2},
+                    "cum_exec_value": "0.00011655",
+                    "cum_exec_fee": "0.00000009",
+                    "timestamp": "{3}",
+                    "take_profit": "0",
+                    "stop_loss": "0",
+                    "trailing_stop": "0",
+                    "last_exec_price": "8579.5",
+                    "reduce_only": false,
+                    "close_on_trigger": false
+                }}]
+            }}"#,
+            status,
+            10 - cum_exec_qty,
+            cum_exec_qty,
+            timestamp
+        )
+    };
+
+    let mut store = Store::default();
+    store.apply(&parse(&order("New", 0, "2020-01-14T14:09:31.778Z"))?);
+    store.apply(&parse(&order(
+        "PartiallyFilled",
+        4,
+        "2020-01-14T14:09:32.778Z",
+    ))?);
+    // Out of order, older than what is stored.
+    store.apply(&parse(&order("New", 0, "2020-01-14T14:09:31.778Z"))?);
+
+    let open = store.open_orders("BTCUSD");
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].order_status, OrderStatus::PartiallyFilled);
+    assert_eq!(open[0].leaves_qty, 6.0);
+    assert_eq!(store.order_by_link_id("entry-1").unwrap().cum_exec_qty, 4.0);
+
+    store.apply(&parse(&order("Filled", 10, "2020-01-14T14:09:33.778Z"))?);
+    assert!(store.open_orders("BTCUSD").is_empty());
+    assert!(store.order_by_link_id("entry-1").is_none());
+
+    // Conditional orders come with their stop order status.
+    store.apply(&parse(&order(
+        "Untriggered",
+        0,
+        "2020-01-14T14:09:34.778Z",
+    ))?);
+    assert_eq!(
+        store.open_orders("BTCUSD")[0].order_status,
+        OrderStatus::Untriggered
+    );
+    store.apply(&parse(&order(
+        "Deactivated",
+        0,
+        "2020-01-14T14:09:35.778Z",
+    ))?);
+    assert!(store.open_orders("BTCUSD").is_empty());
+
+    // Orders may have changed while disconnected.
+    store.apply(&parse(&order("New", 0, "2020-01-14T14:09:36.778Z"))?);
+    store.apply(&WsEvent::Reconnected);
+    assert!(store.open_orders("BTCUSD").is_empty());
+    assert!(store.order_by_link_id("entry-1").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_stop_order_events() -> common::BEResult {
+    common::init();
+
+    let stop_order = |status: &str, timestamp: &str| {
+        format!(
+            r#"{{
+                "topic": "stop_order",
+                "data": [{{
+                    "order_id": "xxxxxxxx-xxxx-xxxx-98fb-335aaa6c613b",
+                    "order_link_id": "",
+                    "user_id": 1,
+                    "symbol": "BTCUSD",
+                    "side": "Buy",
+                    "order_type": "Limit",
+                    "price": "8584.5",
+                    "qty": 1,
+                    "time_in_force": "ImmediateOrCancel",
+                    "create_type": "CreateByStopOrder",
+                    "cancel_type": "",
+                    "order_status": "{}",
+                    "stop_order_type": "StopLoss",
+                    "trigger_by": "MarkPrice",
+                    "trigger_price": "8584.5",
+                    "close_on_trigger": false,
+                    "timestamp": "{}",
+                    "take_profit": 10000,
+                    "stop_loss": 7500
+                }}]
+            }}"#,
+            status, timestamp
+        )
+    };
+
+    let mut store = Store::default();
+    store.apply(&parse(&stop_order(
+        "Untriggered",
+        "2020-01-14T14:11:22.062Z",
+    ))?);
+
+    let pending = store.stop_orders("BTCUSD");
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].stop_order_type, StopOrderType::StopLoss);
+    assert_eq!(pending[0].trigger_by, Trigger::MarkPrice);
+    assert_eq!(pending[0].stop_px, 8584.5);
+
+    store.apply(&parse(&stop_order(
+        "Triggered",
+        "2020-01-14T14:12:22.062Z",
+    ))?);
+    assert!(store.stop_orders("BTCUSD").is_empty());
+    let triggered = store.take_triggered_stop_orders();
+    assert_eq!(triggered[0].order_status, StopOrderStatus::Triggered);
+    assert!(store.take_triggered_stop_orders().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn apply_wallet_events() -> common::BEResult {
+    common::init();
+
+    let wallet = |balance: f64| {
+        format!(
+            r#"{{
+                "topic": "wallet",
+                "data": [{{
+                    "user_id": 738713,
+                    "coin": "BTC",
+                    "wallet_balance": {0},
+                    "available_balance": {0}
+                }}]
+            }}"#,
+            balance
+        )
+    };
+
+    let mut store = Store::default();
+    store.apply(&parse(&wallet(1.5))?);
+    store.apply(&parse(&wallet(1.25))?);
+
+    assert_eq!(store.wallet("BTC").unwrap().wallet_balance, 1.25);
+    let history = store.wallet_history("BTC").unwrap();
+    assert_eq!(history.len(), 2);
+    assert!(history[0].timestamp <= history[1].timestamp);
+    assert!(store.wallet("USDT").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn apply_linear_events() -> common::BEResult {
+    common::init();
+
+    let snapshot = r#"
+        {
+            "topic": "orderBookL2_25.BTCUSDT",
+            "type": "snapshot",
+            "data": {
+                "order_book": [{
+                    "price": "2999.00",
+                    "symbol": "BTCUSDT",
+                    "id": "29990000",
+                    "side": "Buy",
+                    "size": 0.125
+                }, {
+                    "price": "3001.00",
+                    "symbol": "BTCUSDT",
+                    "id": "30010000",
+                    "side": "Sell",
+                    "size": 10
+                }]
+            },
+            "cross_seq": "9188818",
+            "timestamp_e6": "1594978545318476"
+        }"#;
+    let candle = r#"
+        {
+            "topic": "candle.1.BTCUSDT",
+            "data": [{
+                "start": 1588071660,
+                "end": 1588071720,
+                "open": 7744.5,
+                "close": 7745.5,
+                "high": 7745.5,
+                "low": 7744,
+                "volume": "33.051",
+                "turnover": "255979.995",
+                "confirm": true,
+                "cross_seq": 71947372,
+                "timestamp": 1588071717325846
+            }],
+            "timestamp_e6": 1588071721163975
+        }"#;
+    let wallet = r#"
+        {
+            "topic": "wallet",
+            "data": [{
+                "wallet_balance": 429.80713,
+                "available_balance": 429.67322
+            }]
+        }"#;
+
+    let mut store = Store::default();
+    store.apply(&parse(snapshot)?);
+    store.apply(&parse(candle)?);
+    store.apply(&parse(wallet)?);
+
+    let orderbook = store.orderbook(OrderBookDepth::L25, "BTCUSDT").unwrap();
+    assert_eq!(orderbook.limits[&29990000].size, 0.125);
+    let candles = store.candles("BTCUSDT", Interval::OneMin).unwrap();
+    assert_eq!(candles.confirmed().len(), 1);
+    assert_eq!(store.wallet("USDT").unwrap().available_balance, 429.67322);
+
+    Ok(())
+}
+
+fn parse(msg: &str) -> Result<WsEvent, serde_json::Error> {
+    WsEvent::try_from(serde_json::from_str::<WebSocketResponse>(msg)?)
+}
+
 // #[test]
 // fn store_message_snapshot() -> common::BEResult {
 //     common::init();
@@ -33,7 +576,7 @@ use tokio::time::{sleep, Duration};
 //             "type": "snapshot"
 //         }"#;
 
-//     let res: WebsocketResponse = serde_json::from_str(data)?;
+//     let res: WebSocketResponse = serde_json::from_str(data)?;
 //     store::store_message(res);
 
 //     Ok(())
@@ -74,7 +617,7 @@ use tokio::time::{sleep, Duration};
 //             "timestamp_e6": 1617702691138576
 //         }"#;
 
-//     let res: WebsocketResponse = serde_json::from_str(data)?;
+//     let res: WebSocketResponse = serde_json::from_str(data)?;
 //     store::store_message(res);
 
 //     debug!("{:#?}", store::take_orderbook());
@@ -102,7 +645,7 @@ use tokio::time::{sleep, Duration};
 //             }]
 //         }"#;
 
-//     let res: WebsocketResponse = serde_json::from_str(data)?;
+//     let res: WebSocketResponse = serde_json::from_str(data)?;
 //     store::store_message(res);
 
 //     debug!("{:#?}", store::take_trading_records());
@@ -119,7 +662,7 @@ use tokio::time::{sleep, Duration};
 //         secret: env::var("API_SECRET").unwrap(),
 //     };
 
-//     let mut ws = WebsocketBuilder::new()
+//     let mut ws = WebSocketBuilder::new()
 //         .endpoint(Endpoint::MAINNET)
 //         .api(api)
 //         .build()
@@ -1,7 +1,7 @@
 // mod common;
 // extern crate bybit_rs;
 // // use async_std::future::timeout;
-// use bybit_rs::websocket::{Endpoint, Topic, WebsocketBuilder, API};
+// use bybit_rs::websocket::{Endpoint, Topic, WebSocketBuilder, API};
 // use std::env;
 // // use std::time::Duration;
 // use log::debug;
@@ -17,7 +17,7 @@
 //         secret: env::var("API_SECRET").unwrap(),
 //     };
 
-//     let _ = WebsocketBuilder::new()
+//     let _ = WebSocketBuilder::new()
 //         .endpoint(Endpoint::MAINNET)
 //         .api(api)
 //         .build();
@@ -33,14 +33,14 @@
 //         secret: env::var("API_SECRET").unwrap(),
 //     };
 
-//     let mut ws = WebsocketBuilder::new()
+//     let mut ws = WebSocketBuilder::new()
 //         .endpoint(Endpoint::MAINNET)
 //         .api(api)
 //         .build()
 //         .await;
 
 //     assert!(ws
-//         .subscribe(vec![Topic::OrderBook200, Topic::Trade])
+//         .subscribe(vec![Topic::orderbook(200), Topic::Trade])
 //         .await
 //         .is_ok());
 // }
@@ -55,7 +55,7 @@
 //         secret: env::var("API_SECRET").unwrap(),
 //     };
 
-//     let mut ws = WebsocketBuilder::new()
+//     let mut ws = WebSocketBuilder::new()
 //         .endpoint(Endpoint::MAINNET)
 //         .api(api)
 //         .build()
@@ -74,12 +74,12 @@
 //         secret: env::var("API_SECRET").unwrap(),
 //     };
 
-//     let mut ws = WebsocketBuilder::new()
+//     let mut ws = WebSocketBuilder::new()
 //         .endpoint(Endpoint::MAINNET)
 //         .api(api)
 //         .build()
 //         .await;
-//     ws.subscribe(vec![Topic::OrderBook200, Topic::Trade])
+//     ws.subscribe(vec![Topic::orderbook(200), Topic::Trade])
 //         .await
 //         .unwrap();
 
@@ -95,12 +95,12 @@
 //         secret: env::var("API_SECRET").unwrap(),
 //     };
 
-//     let mut ws = WebsocketBuilder::new()
+//     let mut ws = WebSocketBuilder::new()
 //         .endpoint(Endpoint::MAINNET)
 //         .api(api)
 //         .build()
 //         .await;
-//     ws.subscribe(vec![Topic::OrderBook200, Topic::Trade])
+//     ws.subscribe(vec![Topic::orderbook(200), Topic::Trade])
 //         .await?;
 
 //     // assert!(timeout(Duration::from_secs(10), ws.run_forever())